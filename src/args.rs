@@ -2,14 +2,127 @@
 #[clap(about = "HTML formatter")]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    #[clap(help = "Files, directories or glob patterns to format, use - to read from stdin")]
     pub files: Vec<String>,
 
+    #[clap(long)]
+    #[clap(help = "Don't read formatting options from .editorconfig files")]
+    pub no_editorconfig: bool,
+
+    #[clap(long)]
+    #[clap(help = "Path to ignore file used instead of .htmlformatterignore, can be repeated")]
+    pub ignore_path: Vec<String>,
+
+    #[clap(long)]
+    #[clap(help = "Also skip files ignored by .gitignore")]
+    pub use_gitignore: bool,
+
+    #[clap(long, value_delimiter = ',', default_value = "html,htm")]
+    #[clap(help = "Extensions of files collected from directories")]
+    pub extensions: Vec<String>,
+
+    #[clap(long, conflicts_with = "files")]
+    #[clap(help = "Read content from stdin and write formatted content to stdout")]
+    pub stdin: bool,
+
+    #[clap(long)]
+    #[clap(help = "Path of the file read from stdin, used to resolve config and ignore rules")]
+    pub stdin_filepath: Option<String>,
+
     #[clap(long, short = 's')]
     #[clap(help = "Indent style: space or tab")]
     #[clap(value_parser = ["tab", "space"])]
     pub indent_style: Option<String>,
 
-    #[clap(long, short = 'l', default_value_t = 80)]
-    #[clap(help = "Max length of line")]
-    pub max_line_length: i32,
+    #[clap(long)]
+    #[clap(help = "Number of spaces per indent level [default: 2]")]
+    #[clap(value_parser = clap::value_parser!(u16).range(1..))]
+    pub indent_size: Option<u16>,
+
+    #[clap(long, short = 'l')]
+    #[clap(help = "Max length of line [default: 80]")]
+    #[clap(value_parser = clap::value_parser!(i32).range(1..))]
+    pub max_line_length: Option<i32>,
+
+    #[clap(long, short = 'e')]
+    #[clap(help = "End of line: lf, crlf or auto to keep the first line ending of the file")]
+    #[clap(value_parser = ["lf", "crlf", "auto"])]
+    pub end_of_line: Option<String>,
+
+    #[clap(long)]
+    #[clap(help = "Put every tag attribute on its own line")]
+    pub single_attribute_per_line: bool,
+
+    #[clap(long)]
+    #[clap(help = "Max number of consecutive blank lines kept between nodes [default: 1]")]
+    pub max_blank_lines: Option<usize>,
+
+    #[clap(long)]
+    #[clap(help = "Don't end output with a line break")]
+    pub no_insert_final_newline: bool,
+
+    #[clap(long)]
+    #[clap(help = "Keep whitespace at the end of text lines")]
+    pub no_trim_trailing_whitespace: bool,
+
+    #[clap(long, short = 'j')]
+    #[clap(help = "Number of files formatted in parallel, defaults to number of CPUs")]
+    pub jobs: Option<usize>,
+
+    #[clap(long, conflicts_with = "diff")]
+    #[clap(help = "Check if files are formatted without writing them, exit with 1 otherwise")]
+    pub check: bool,
+
+    #[clap(long)]
+    #[clap(help = "Print unified diff of changes without writing files")]
+    pub diff: bool,
+
+    #[clap(long)]
+    #[clap(help = "Don't write files whose formatted content builds a different document tree")]
+    pub safe: bool,
+
+    #[clap(long, conflicts_with_all = ["safe", "range_start", "range_end"])]
+    #[clap(help = "Minify instead of formatting")]
+    pub minify: bool,
+
+    #[clap(long, value_delimiter = ',', requires = "minify")]
+    #[clap(help = "Minify optimizations to turn off")]
+    #[clap(value_parser = ["whitespace", "comments", "optional-end-tags", "attribute-quotes", "boolean-attributes", "default-type"])]
+    pub minify_disable: Vec<String>,
+
+    #[clap(long, value_name = "N")]
+    #[clap(
+        help = "Format only nodes covering the range from this byte offset (or line with --range-lines)"
+    )]
+    pub range_start: Option<usize>,
+
+    #[clap(long, value_name = "N")]
+    #[clap(help = "End byte offset (or inclusive line with --range-lines) of the range to format")]
+    pub range_end: Option<usize>,
+
+    #[clap(long)]
+    #[clap(help = "Treat --range-start and --range-end as line numbers starting from 1")]
+    pub range_lines: bool,
+
+    #[clap(long, conflicts_with_all = ["range_start", "range_end"])]
+    #[clap(help = "Format files twice and report files changed by the second pass")]
+    pub verify: bool,
+
+    #[clap(long, default_value_t = 3)]
+    #[clap(help = "Number of context lines in diff")]
+    pub diff_context: usize,
+
+    #[clap(long, default_value = "auto")]
+    #[clap(help = "Colorize diff output: auto, always or never")]
+    #[clap(value_parser = ["auto", "always", "never"])]
+    pub color: String,
+
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["files", "stdin"])]
+    #[clap(help = "Print options resolved for the file with their sources instead of formatting")]
+    pub print_config: Option<String>,
+
+    #[clap(long, default_value = "toml")]
+    #[clap(help = "Format of --print-config output: toml or json")]
+    #[clap(value_parser = ["toml", "json"])]
+    pub print_config_format: String,
 }
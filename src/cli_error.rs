@@ -0,0 +1,84 @@
+use html_formatter::FormatError;
+use std::path::PathBuf;
+use std::{fmt, io};
+
+/// Errors of the binary, formatting errors of the library are wrapped in `Format`
+#[derive(Debug)]
+pub enum CliError {
+    Io(io::Error),
+    Pattern(glob::PatternError),
+    /// Glob pattern which matched no paths, likely a typo
+    NoMatches,
+    Ignore(ignore::Error),
+    Config {
+        path: PathBuf,
+        key: Option<String>,
+        message: String,
+    },
+    InvalidUtf8(std::str::Utf8Error),
+    Format(FormatError),
+    /// Formatting a file panicked, other files are still formatted
+    Panic(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::Pattern(error) => write!(f, "invalid glob pattern: {}", error),
+            Self::NoMatches => write!(f, "pattern matched no files"),
+            Self::Ignore(error) => write!(f, "invalid ignore file: {}", error),
+            Self::Config {
+                path,
+                key: Some(key),
+                message,
+            } => write!(
+                f,
+                "invalid config {}: `{}` {}",
+                path.display(),
+                key,
+                message
+            ),
+            Self::Config {
+                path,
+                key: None,
+                message,
+            } => write!(f, "invalid config {}: {}", path.display(), message),
+            Self::InvalidUtf8(error) => write!(f, "file is not valid UTF-8: {}", error),
+            Self::Format(error) => write!(f, "{}", error),
+            Self::Panic(message) => write!(f, "formatter panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<glob::PatternError> for CliError {
+    fn from(error: glob::PatternError) -> Self {
+        Self::Pattern(error)
+    }
+}
+
+impl From<glob::GlobError> for CliError {
+    fn from(error: glob::GlobError) -> Self {
+        Self::Io(error.into())
+    }
+}
+
+impl From<std::str::Utf8Error> for CliError {
+    fn from(error: std::str::Utf8Error) -> Self {
+        Self::InvalidUtf8(error)
+    }
+}
+
+impl From<FormatError> for CliError {
+    fn from(error: FormatError) -> Self {
+        Self::Format(error)
+    }
+}
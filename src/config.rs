@@ -1,36 +1,291 @@
-use crate::models::IndentStyle;
+use html_formatter::minify::MinifyOptions;
+use html_formatter::models::{EndOfLine, IndentStyle};
+use html_formatter::options::PartialOptions;
+use html_formatter::range;
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 #[derive(Debug)]
 pub struct Config {
-    pub indent_style: IndentStyle,
-    pub max_line_length: i32,
+    /// Formatting options set by CLI flags, they override config files
+    pub options: PartialOptions,
+    /// Use `.editorconfig` properties for options unset by config files
+    pub editorconfig: bool,
     pub files: Vec<String>,
+    pub extensions: Vec<String>,
+    pub ignore_paths: Vec<String>,
+    pub use_gitignore: bool,
+    pub stdin: bool,
+    pub stdin_filepath: Option<String>,
+    pub mode: Mode,
+    /// Format files twice and fail if the second pass changes them
+    pub verify: bool,
+    /// Compare document trees of the input and the output before writing
+    pub safe: bool,
+    /// Format only nodes covering this range of the single input file
+    pub range: Option<TextRange>,
+    /// Minify instead of formatting
+    pub minify: Option<MinifyOptions>,
+    pub jobs: usize,
+    pub diff_context: usize,
+    pub color: bool,
+    /// Print options resolved for this path instead of formatting
+    pub print_config: Option<String>,
+    pub print_config_format: ConfigFormat,
 }
 
 impl Config {
     pub fn new(files: Vec<String>) -> Self {
         Config {
-            indent_style: IndentStyle::Tab,
-            max_line_length: 80,
+            options: PartialOptions::default(),
+            editorconfig: true,
             files,
+            extensions: vec!["html".to_owned(), "htm".to_owned()],
+            ignore_paths: vec![],
+            use_gitignore: false,
+            stdin: false,
+            stdin_filepath: None,
+            mode: Mode::Write,
+            verify: false,
+            safe: false,
+            range: None,
+            minify: None,
+            jobs: 1,
+            diff_context: 3,
+            color: false,
+            print_config: None,
+            print_config_format: ConfigFormat::Toml,
         }
     }
 }
 
 impl Config {
     pub fn set_indent_style(mut self, indent_style: Option<String>) -> Self {
-        self.indent_style = match indent_style {
-            Some(indent_style) => IndentStyle::from_str(indent_style.as_str()).unwrap_or_default(),
-            None => IndentStyle::default(),
+        self.options.indent_style = indent_style
+            .map(|indent_style| IndentStyle::from_str(indent_style.as_str()).unwrap_or_default());
+
+        self
+    }
+
+    pub fn set_indent_size(mut self, indent_size: Option<usize>) -> Self {
+        self.options.indent_size = indent_size;
+
+        self
+    }
+
+    pub fn set_max_line_length(mut self, max_line_length: Option<i32>) -> Self {
+        self.options.max_line_length = max_line_length;
+
+        self
+    }
+
+    pub fn set_single_attribute_per_line(mut self, single_attribute_per_line: bool) -> Self {
+        // Without the flag the value comes from config files
+        self.options.single_attribute_per_line = single_attribute_per_line.then_some(true);
+
+        self
+    }
+
+    pub fn set_max_blank_lines(mut self, max_blank_lines: Option<usize>) -> Self {
+        self.options.max_blank_lines = max_blank_lines;
+
+        self
+    }
+
+    pub fn set_insert_final_newline(mut self, insert_final_newline: bool) -> Self {
+        // Without the flag the value comes from config files
+        self.options.insert_final_newline = (!insert_final_newline).then_some(false);
+
+        self
+    }
+
+    pub fn set_trim_trailing_whitespace(mut self, trim_trailing_whitespace: bool) -> Self {
+        self.options.trim_trailing_whitespace = (!trim_trailing_whitespace).then_some(false);
+
+        self
+    }
+
+    pub fn set_editorconfig(mut self, editorconfig: bool) -> Self {
+        self.editorconfig = editorconfig;
+
+        self
+    }
+
+    pub fn set_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.extensions = extensions;
+
+        self
+    }
+
+    pub fn set_ignore(mut self, ignore_paths: Vec<String>, use_gitignore: bool) -> Self {
+        self.ignore_paths = ignore_paths;
+        self.use_gitignore = use_gitignore;
+
+        self
+    }
+
+    pub fn set_stdin(mut self, stdin: bool, stdin_filepath: Option<String>) -> Self {
+        self.stdin = stdin;
+        self.stdin_filepath = stdin_filepath;
+
+        self
+    }
+
+    pub fn set_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+
+        self
+    }
+
+    pub fn set_verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+
+        self
+    }
+
+    pub fn set_safe(mut self, safe: bool) -> Self {
+        self.safe = safe;
+
+        self
+    }
+
+    pub fn set_range(mut self, start: Option<usize>, end: Option<usize>, lines: bool) -> Self {
+        self.range = (start.is_some() || end.is_some()).then_some(TextRange { start, end, lines });
+
+        self
+    }
+
+    pub fn set_minify(mut self, minify: bool, disabled: Vec<String>) -> Self {
+        self.minify = minify.then(|| {
+            let enabled = |name: &str| !disabled.iter().any(|disabled| disabled == name);
+
+            MinifyOptions::new()
+                .set_collapse_whitespace(enabled("whitespace"))
+                .set_remove_comments(enabled("comments"))
+                .set_remove_optional_end_tags(enabled("optional-end-tags"))
+                .set_remove_attribute_quotes(enabled("attribute-quotes"))
+                .set_collapse_boolean_attributes(enabled("boolean-attributes"))
+                .set_remove_default_type(enabled("default-type"))
+        });
+
+        self
+    }
+
+    pub fn set_jobs(mut self, jobs: Option<usize>) -> Self {
+        self.jobs = match jobs {
+            Some(jobs) => jobs.max(1),
+            None => std::thread::available_parallelism().map_or(1, |jobs| jobs.get()),
         };
 
         self
     }
 
-    pub fn set_max_line_length(mut self, max_line_length: i32) -> Self {
-        self.max_line_length = max_line_length;
+    pub fn set_diff_context(mut self, diff_context: usize) -> Self {
+        self.diff_context = diff_context;
+
+        self
+    }
+
+    pub fn set_color(mut self, color: bool) -> Self {
+        self.color = color;
+
+        self
+    }
+
+    pub fn set_print_config(mut self, path: Option<String>, format: String) -> Self {
+        self.print_config = path;
+        self.print_config_format = ConfigFormat::from_str(format.as_str()).unwrap_or_default();
+
+        self
+    }
+
+    pub fn set_end_of_line(mut self, end_of_line: Option<String>) -> Self {
+        self.options.end_of_line = end_of_line
+            .map(|end_of_line| EndOfLine::from_str(end_of_line.as_str()).unwrap_or_default());
 
         self
     }
 }
+
+/// Range of `--range-start` and `--range-end`, unset bounds are the start and
+/// the end of content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextRange {
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    /// Bounds are line numbers starting from 1 instead of byte offsets
+    pub lines: bool,
+}
+
+impl TextRange {
+    /// Byte range in `content`, bounds are clamped to its length
+    pub fn resolve(&self, content: &str) -> Range<usize> {
+        if self.lines {
+            return range::line_range(
+                content,
+                self.start.unwrap_or(1),
+                self.end.unwrap_or(usize::MAX - 1),
+            );
+        }
+
+        let end = self.end.unwrap_or(content.len()).min(content.len());
+
+        self.start.unwrap_or(0).min(end)..end
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Write formatted content back to files
+    #[default]
+    Write,
+    /// Only report files which are not formatted
+    Check,
+    /// Print unified diff of changes instead of writing files
+    Diff,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Json,
+}
+
+impl FromStr for ConfigFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "toml" => Ok(Self::Toml),
+            "json" => Ok(Self::Json),
+            _ => Err("Not valid config format"),
+        }
+    }
+}
+
+/// Where options of a `PartialOptions` layer come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionSource {
+    Default,
+    EditorConfig(PathBuf),
+    ConfigFile(PathBuf),
+    Override { path: PathBuf, index: usize },
+    Cli,
+}
+
+impl fmt::Display for OptionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::EditorConfig(path) => write!(f, "editorconfig {}", path.display()),
+            Self::ConfigFile(path) => write!(f, "config file {}", path.display()),
+            Self::Override { path, index } => {
+                write!(f, "config file {} overrides[{}]", path.display(), index)
+            }
+            Self::Cli => write!(f, "CLI flag"),
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::cli_error::CliError;
+use crate::dir_cache::DirCache;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use html_formatter::options::PartialOptions;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Config file names in the order of priority, `.htmlformatterrc` may be JSON or TOML
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["htmlformatter.toml", ".htmlformatterrc"];
+
+#[derive(Debug)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub options: PartialOptions,
+    /// Applied in order after `options` to files matched by their patterns
+    pub overrides: Vec<Override>,
+}
+
+#[derive(Debug)]
+pub struct Override {
+    pub files: Vec<String>,
+    matcher: GlobSet,
+    pub options: PartialOptions,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawOverride {
+    files: OneOrMany,
+    options: PartialOptions,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        log::trace!("load config file {}", path.display());
+
+        let error = |key: Option<String>, message: String| CliError::Config {
+            path: path.to_path_buf(),
+            key,
+            message,
+        };
+
+        let content = fs::read_to_string(path).map_err(|e| error(None, e.to_string()))?;
+        let is_json = path.extension().is_none() && content.trim_start().starts_with('{');
+
+        let mut value: serde_json::Value = if is_json {
+            serde_json::from_str(&content).map_err(|e| error(None, e.to_string()))?
+        } else {
+            toml::from_str(&content).map_err(|e| error(None, e.to_string()))?
+        };
+
+        // Options are flat in the root, so `overrides` is taken out to keep unknown keys denied
+        let raw_overrides = match value.as_object_mut() {
+            Some(object) => object.remove("overrides"),
+            None => None,
+        };
+
+        let options: PartialOptions = deserialize(value, "").map_err(|(k, m)| error(k, m))?;
+        options
+            .validate()
+            .map_err(|(key, message)| error(Some(key.to_owned()), message))?;
+
+        let raw_overrides: Vec<RawOverride> = match raw_overrides {
+            Some(raw_overrides) => {
+                deserialize(raw_overrides, "overrides").map_err(|(k, m)| error(k, m))?
+            }
+            None => vec![],
+        };
+
+        let mut overrides = vec![];
+        for (index, raw_override) in raw_overrides.into_iter().enumerate() {
+            let files = match raw_override.files {
+                OneOrMany::One(file) => vec![file],
+                OneOrMany::Many(files) => files,
+            };
+            let matcher = build_matcher(&files)
+                .map_err(|message| error(Some(format!("overrides[{}].files", index)), message))?;
+
+            raw_override.options.validate().map_err(|(key, message)| {
+                error(
+                    Some(format!("overrides[{}].options.{}", index, key)),
+                    message,
+                )
+            })?;
+
+            overrides.push(Override {
+                files,
+                matcher,
+                options: raw_override.options,
+            });
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            options,
+            overrides,
+        })
+    }
+
+    /// Overrides matching `path` with their indexes, patterns are relative to the
+    /// config file directory
+    pub fn overrides_for<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> impl Iterator<Item = (usize, &'a Override)> {
+        let relative_path = self
+            .path
+            .parent()
+            .and_then(|dir| path.strip_prefix(dir).ok());
+
+        self.overrides.iter().enumerate().filter(move |(_, item)| {
+            relative_path.is_some_and(|relative_path| item.matcher.is_match(relative_path))
+        })
+    }
+}
+
+/// Deserializes with the path of the invalid key, `prefix` is the path of `value`
+fn deserialize<T: DeserializeOwned>(
+    value: serde_json::Value,
+    prefix: &str,
+) -> Result<T, (Option<String>, String)> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let key = match e.path().to_string().as_str() {
+            "." if prefix.is_empty() => None,
+            "." => Some(prefix.to_owned()),
+            key if prefix.is_empty() => Some(key.to_owned()),
+            key if key.starts_with('[') => Some(format!("{}{}", prefix, key)),
+            key => Some(format!("{}.{}", prefix, key)),
+        };
+
+        (key, e.into_inner().to_string())
+    })
+}
+
+/// Patterns without `/` match file names in any directory, like in gitignore
+fn build_matcher(files: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in files {
+        let pattern = pattern.trim_start_matches("./");
+        let pattern = if pattern.contains('/') {
+            pattern.to_owned()
+        } else {
+            format!("**/{}", pattern)
+        };
+
+        let glob = GlobBuilder::new(&pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        builder.add(glob);
+    }
+
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Finds the nearest config file of a directory
+pub struct ConfigFiles {
+    cache: DirCache<Option<Arc<ConfigFile>>>,
+}
+
+impl ConfigFiles {
+    pub fn new() -> Self {
+        Self {
+            cache: DirCache::new(),
+        }
+    }
+
+    pub fn find(&self, dir: &Path) -> Result<Option<Arc<ConfigFile>>, CliError> {
+        self.cache.get_or_load(dir, || {
+            let config_path = CONFIG_FILE_NAMES
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file());
+
+            match (config_path, dir.parent()) {
+                (Some(config_path), _) => Ok(Some(Arc::new(ConfigFile::load(&config_path)?))),
+                (None, Some(parent)) => self.find(parent),
+                (None, None) => Ok(None),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConfigFile;
+    use crate::temp_dir::TempDir;
+    use html_formatter::models::{EndOfLine, IndentStyle};
+
+    #[test]
+    fn load_toml_and_json() {
+        let dir = TempDir::new("load_config");
+
+        let toml = dir.write(
+            "htmlformatter.toml",
+            "indent_style = \"space\"\nmax_line_length = 100\n",
+        );
+        let options = ConfigFile::load(&toml).unwrap().options;
+        assert_eq!(options.indent_style, Some(IndentStyle::Space));
+        assert_eq!(options.max_line_length, Some(100));
+
+        let json = dir.write(".htmlformatterrc", "{ \"end_of_line\": \"crlf\" }");
+        let options = ConfigFile::load(&json).unwrap().options;
+        assert_eq!(options.end_of_line, Some(EndOfLine::Crlf));
+        assert_eq!(options.indent_style, None);
+    }
+
+    #[test]
+    fn overrides_match_relative_paths() {
+        let dir = TempDir::new("overrides");
+        let path = dir.write(
+            ".htmlformatterrc",
+            r#"{
+                "overrides": [
+                    { "files": "emails/**/*.html", "options": { "indent_size": 4 } },
+                    { "files": ["*.component.html"], "options": { "single_attribute_per_line": true } }
+                ]
+            }"#,
+        );
+        let config_file = ConfigFile::load(&path).unwrap();
+
+        let matched = |file: &str| {
+            let file = dir.path().join(file);
+
+            config_file
+                .overrides_for(&file)
+                .map(|(_, item)| item.files[0].clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matched("emails/welcome/index.html"), ["emails/**/*.html"]);
+        assert_eq!(matched("app/list.component.html"), ["*.component.html"]);
+        assert!(matched("index.html").is_empty());
+        assert!(matched("../emails/index.html").is_empty());
+    }
+
+    #[test]
+    fn errors_name_file_and_key() {
+        let dir = TempDir::new("config_errors");
+
+        let path = dir.write("htmlformatter.toml", "indent_style = \"tabs\"\n");
+        let error = ConfigFile::load(&path).unwrap_err().to_string();
+        assert!(error.starts_with(&format!(
+            "invalid config {}: `indent_style` unknown variant `tabs`",
+            path.display()
+        )));
+
+        let path = dir.write(".htmlformatterrc", "max_line_length = 0\n");
+        let error = ConfigFile::load(&path).unwrap_err().to_string();
+        assert!(error.ends_with("`max_line_length` must be positive, got 0"));
+
+        let path = dir.write(
+            "htmlformatter.toml",
+            "[[overrides]]\nfiles = \"*.html\"\noptions = { indent_size = \"4\" }\n",
+        );
+        let error = ConfigFile::load(&path).unwrap_err().to_string();
+        assert!(error.contains("`overrides[0].options.indent_size` invalid type"));
+
+        let path = dir.write(".htmlformatterrc", "{ \"indent\": 2 }");
+        let error = ConfigFile::load(&path).unwrap_err().to_string();
+        assert!(error.contains("unknown field `indent`"));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::options::Options;
+
+/// Formats the content of a `<style>` element: one declaration per line, blocks of
+/// rules and at-rules indented one level deeper. Strings are kept as is, lines are
+/// joined with LF and the first one isn't indented.
+pub fn format_css(css: &str, options: &Options) -> String {
+    let mut formatter = CssFormatter {
+        options,
+        lines: vec![],
+        buffer: String::new(),
+        depth: 0,
+        line_breaks: 0,
+    };
+    let mut paren_depth = 0;
+    let mut rest = css;
+
+    while let Some(char) = rest.chars().next() {
+        let mut len = char.len_utf8();
+
+        match char {
+            '/' if rest.starts_with("/*") => {
+                len = rest[2..].find("*/").map_or(rest.len(), |end| end + 4);
+                formatter.push_comment(&rest[..len]);
+            }
+            '"' | '\'' => {
+                len = string_len(rest, char);
+                formatter.buffer.push_str(&rest[..len]);
+            }
+            '(' => {
+                paren_depth += 1;
+                formatter.buffer.push(char);
+            }
+            ')' => {
+                paren_depth = (paren_depth - 1).max(0);
+                formatter.buffer.push(char);
+            }
+            '{' if paren_depth == 0 => formatter.open_block(),
+            '}' if paren_depth == 0 => formatter.close_block(),
+            ';' if paren_depth == 0 => formatter.end_statement(),
+            ',' if paren_depth == 0 => {
+                formatter.buffer.truncate(formatter.buffer.trim_end().len());
+                formatter.buffer.push_str(", ");
+            }
+            _ if char.is_whitespace() => formatter.push_whitespace(char),
+            _ => formatter.buffer.push(char),
+        }
+
+        rest = &rest[len..];
+    }
+
+    formatter.finish()
+}
+
+struct CssFormatter<'a> {
+    options: &'a Options,
+    lines: Vec<String>,
+    /// Selector, at-rule prelude or declaration read so far with whitespace collapsed
+    buffer: String,
+    depth: i32,
+    /// Line breaks since the last written line, blank lines between statements are kept
+    line_breaks: usize,
+}
+
+impl CssFormatter<'_> {
+    fn push_line(&mut self, text: &str, keep_blank_lines: bool) {
+        let after_open_block = self.lines.last().is_none_or(|line| line.ends_with('{'));
+
+        if keep_blank_lines && !after_open_block {
+            let blank_lines = self
+                .line_breaks
+                .saturating_sub(1)
+                .min(self.options.max_blank_lines);
+
+            self.lines
+                .extend(std::iter::repeat_n(String::new(), blank_lines));
+        }
+
+        self.lines.push(if text.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", self.options.indent(self.depth), text)
+        });
+        self.line_breaks = 0;
+    }
+
+    fn push_whitespace(&mut self, char: char) {
+        if self.buffer.is_empty() {
+            if char == '\n' {
+                self.line_breaks += 1;
+            }
+        } else if !self.buffer.ends_with(' ') {
+            self.buffer.push(' ');
+        }
+    }
+
+    /// Comments between statements get their own lines, inner lines are trimmed and
+    /// `*` lines are aligned under `/*`. Comments inside a statement are kept inline.
+    fn push_comment(&mut self, comment: &str) {
+        if !self.buffer.is_empty() {
+            self.buffer
+                .push_str(&comment.split_whitespace().collect::<Vec<_>>().join(" "));
+
+            return;
+        }
+
+        for (index, line) in comment.lines().map(str::trim).enumerate() {
+            if index == 0 {
+                self.push_line(line, true);
+            } else if line.starts_with('*') {
+                self.push_line(&format!(" {}", line), false);
+            } else {
+                self.push_line(line, false);
+            }
+        }
+    }
+
+    fn take_buffer(&mut self) -> String {
+        let statement = self.buffer.trim_end().to_owned();
+        self.buffer.clear();
+
+        statement
+    }
+
+    fn open_block(&mut self) {
+        let prelude = self.take_buffer();
+
+        if prelude.is_empty() {
+            self.push_line("{", true);
+        } else {
+            self.push_line(&format!("{} {{", prelude), true);
+        }
+        self.depth += 1;
+    }
+
+    /// Last declaration of a block gets `;` too
+    fn close_block(&mut self) {
+        self.end_statement();
+        self.depth = (self.depth - 1).max(0);
+        self.push_line("}", false);
+    }
+
+    fn end_statement(&mut self) {
+        let statement = self.take_buffer();
+
+        if !statement.is_empty() {
+            self.push_line(&format!("{};", declaration(&statement)), true);
+        }
+    }
+
+    /// Text after the last statement is written as is, e.g. an unterminated rule
+    fn finish(mut self) -> String {
+        let statement = self.take_buffer();
+
+        if !statement.is_empty() {
+            self.push_line(&statement, true);
+        }
+
+        self.lines.join("\n")
+    }
+}
+
+/// `name: value` with a single space after the colon, at-rules are kept as is
+fn declaration(statement: &str) -> String {
+    match statement.split_once(':') {
+        Some((name, value)) if !statement.starts_with('@') => {
+            format!("{}: {}", name.trim_end(), value.trim_start())
+        }
+        _ => statement.to_owned(),
+    }
+}
+
+/// Length of the string starting at `rest` including its quotes
+fn string_len(rest: &str, quote: char) -> usize {
+    let mut escaped = false;
+
+    for (index, char) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if char == '\\' {
+            escaped = true;
+        } else if char == quote {
+            return index + 1;
+        }
+    }
+
+    rest.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_css;
+    use crate::options::Options;
+
+    #[test]
+    fn formats_rules_and_at_rules() {
+        let css = "\n  /* Base\n     * styles */\n  a,b:hover{color:red;font-family:\"A  B\" , serif}\n\n\n\n@import url(\"x;y.css\");\n@media (max-width:600px){ .c>p { margin :0 ; background:url(a.png) } }\n";
+
+        assert_eq!(
+            format_css(css, &Options::new()),
+            "/* Base\n * styles */\na, b:hover {\n\tcolor: red;\n\tfont-family: \"A  B\", serif;\n}\n\n@import url(\"x;y.css\");\n@media (max-width:600px) {\n\t.c>p {\n\t\tmargin: 0;\n\t\tbackground: url(a.png);\n\t}\n}"
+        );
+    }
+}
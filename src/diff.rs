@@ -0,0 +1,78 @@
+use similar::TextDiff;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Builds unified diff between file content and its formatted version
+pub fn unified_diff(
+    path: &str,
+    original: &str,
+    formatted: &str,
+    context_lines: usize,
+    color: bool,
+) -> String {
+    let (original_header, formatted_header) = headers(path);
+    let diff = TextDiff::from_lines(original, formatted)
+        .unified_diff()
+        .context_radius(context_lines)
+        .header(&original_header, &formatted_header)
+        .to_string();
+
+    if color {
+        colorize(&diff)
+    } else {
+        diff
+    }
+}
+
+/// Git-style `a/` and `b/` prefixes, which make no sense for absolute paths
+fn headers(path: &str) -> (String, String) {
+    if path.starts_with('/') {
+        (path.to_owned(), path.to_owned())
+    } else {
+        let path = path.trim_start_matches("./");
+
+        (format!("a/{}", path), format!("b/{}", path))
+    }
+}
+
+fn colorize(diff: &str) -> String {
+    diff.split_inclusive('\n')
+        .map(|line| {
+            let color = if line.starts_with("---") || line.starts_with("+++") {
+                BOLD
+            } else if line.starts_with("@@") {
+                CYAN
+            } else if line.starts_with('-') {
+                RED
+            } else if line.starts_with('+') {
+                GREEN
+            } else {
+                return line.to_owned();
+            };
+
+            match line.strip_suffix('\n') {
+                Some(line) => format!("{}{}{}\n", color, line, RESET),
+                None => format!("{}{}{}", color, line, RESET),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unified_diff;
+
+    #[test]
+    fn diff_with_headers() {
+        let diff = unified_diff("index.html", "<p>\na\n</p>\n", "<p>\n\ta\n</p>\n", 1, false);
+
+        assert_eq!(
+            diff,
+            "--- a/index.html\n+++ b/index.html\n@@ -1,3 +1,3 @@\n <p>\n-a\n+\ta\n </p>\n"
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Values loaded per directory, cached and shared between formatting threads
+pub struct DirCache<T> {
+    cache: Mutex<HashMap<PathBuf, T>>,
+}
+
+impl<T: Clone> DirCache<T> {
+    pub fn new() -> Self {
+        Self {
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cached value of `dir` or the one returned by `load`. The lock isn't held while
+    /// loading, so `load` may get values of parent directories.
+    pub fn get_or_load<E>(&self, dir: &Path, load: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+        if let Some(value) = self.cache.lock().unwrap().get(dir) {
+            return Ok(value.clone());
+        }
+
+        let value = load()?;
+
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(dir.to_path_buf(), value.clone());
+
+        Ok(value)
+    }
+}
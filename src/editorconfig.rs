@@ -0,0 +1,258 @@
+use crate::cli_error::CliError;
+use crate::dir_cache::DirCache;
+use globset::{GlobBuilder, GlobMatcher};
+use html_formatter::models::{EndOfLine, IndentStyle};
+use html_formatter::options::PartialOptions;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const EDITORCONFIG_FILE_NAME: &str = ".editorconfig";
+
+#[derive(Debug)]
+struct EditorConfigFile {
+    dir: PathBuf,
+    root: bool,
+    sections: Vec<Section>,
+}
+
+#[derive(Debug)]
+struct Section {
+    matcher: GlobMatcher,
+    properties: Vec<(String, String)>,
+}
+
+impl EditorConfigFile {
+    fn load(path: &Path) -> Result<Self, CliError> {
+        log::trace!("load editorconfig {}", path.display());
+
+        let content = fs::read_to_string(path)?;
+        let mut root = false;
+        let mut sections: Vec<Section> = vec![];
+        // Properties of a section with invalid glob are skipped
+        let mut skip_section = false;
+
+        for line in content.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                match build_matcher(glob) {
+                    Ok(matcher) => {
+                        skip_section = false;
+                        sections.push(Section {
+                            matcher,
+                            properties: vec![],
+                        });
+                    }
+                    Err(error) => {
+                        log::warn!("{}: invalid section [{}]: {}", path.display(), glob, error);
+                        skip_section = true;
+                    }
+                }
+
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim().to_lowercase();
+
+            match sections.last_mut() {
+                _ if skip_section => {}
+                Some(section) => section.properties.push((key, value)),
+                // Preamble before the first section
+                None if key == "root" => root = value == "true",
+                None => {}
+            }
+        }
+
+        Ok(Self {
+            dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            root,
+            sections,
+        })
+    }
+}
+
+/// Globs with `/` are relative to the `.editorconfig` directory,
+/// others match file names in any directory
+fn build_matcher(glob: &str) -> Result<GlobMatcher, globset::Error> {
+    let glob = match glob.strip_prefix('/') {
+        Some(glob) => glob.to_owned(),
+        None if glob.contains('/') => glob.to_owned(),
+        None => format!("**/{}", glob),
+    };
+
+    Ok(GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()?
+        .compile_matcher())
+}
+
+/// Resolves EditorConfig properties of files
+pub struct EditorConfigs {
+    cache: DirCache<Option<Arc<EditorConfigFile>>>,
+}
+
+impl EditorConfigs {
+    pub fn new() -> Self {
+        Self {
+            cache: DirCache::new(),
+        }
+    }
+
+    /// Options from `.editorconfig` files of `path` directory and its parents up to
+    /// the one with `root = true`. Nearer files and later sections take precedence.
+    /// Options are grouped by the file they come from, farthest file first.
+    pub fn options_for(&self, path: &Path) -> Result<Vec<(PathBuf, PartialOptions)>, CliError> {
+        let mut files = vec![];
+
+        for dir in path.ancestors().skip(1) {
+            if let Some(file) = self.load(dir)? {
+                let root = file.root;
+                files.push(file);
+
+                if root {
+                    break;
+                }
+            }
+        }
+
+        files.reverse();
+
+        // Property values with index of the file they come from
+        let mut properties: HashMap<String, (String, usize)> = HashMap::new();
+
+        for (index, file) in files.iter().enumerate() {
+            let Ok(relative_path) = path.strip_prefix(&file.dir) else {
+                continue;
+            };
+
+            for section in &file.sections {
+                if section.matcher.is_match(relative_path) {
+                    for (key, value) in &section.properties {
+                        properties.insert(key.clone(), (value.clone(), index));
+                    }
+                }
+            }
+        }
+
+        let options = files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| {
+                let file_properties = properties
+                    .iter()
+                    .filter(|(_, (_, file_index))| *file_index == index)
+                    .map(|(key, (value, _))| (key.clone(), value.clone()))
+                    .collect();
+
+                (
+                    file.dir.join(EDITORCONFIG_FILE_NAME),
+                    to_options(&file_properties),
+                )
+            })
+            .filter(|(_, options)| !options.keys().is_empty())
+            .collect();
+
+        Ok(options)
+    }
+
+    fn load(&self, dir: &Path) -> Result<Option<Arc<EditorConfigFile>>, CliError> {
+        self.cache.get_or_load(dir, || {
+            let path = dir.join(EDITORCONFIG_FILE_NAME);
+
+            if path.is_file() {
+                Ok(Some(Arc::new(EditorConfigFile::load(&path)?)))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+}
+
+/// Unknown values and `unset` leave options unset, as the EditorConfig spec requires
+fn to_options(properties: &HashMap<String, String>) -> PartialOptions {
+    let property = |key: &str| properties.get(key).map(String::as_str);
+
+    PartialOptions {
+        indent_style: match property("indent_style") {
+            Some("tab") => Some(IndentStyle::Tab),
+            Some("space") => Some(IndentStyle::Space),
+            _ => None,
+        },
+        indent_size: property("indent_size")
+            .and_then(|value| value.parse().ok())
+            .filter(|indent_size| *indent_size > 0),
+        max_line_length: match property("max_line_length") {
+            Some("off") => Some(i32::MAX),
+            value => value
+                .and_then(|value| value.parse().ok())
+                .filter(|max_line_length| *max_line_length > 0),
+        },
+        end_of_line: match property("end_of_line") {
+            Some("lf") => Some(EndOfLine::Lf),
+            Some("crlf") => Some(EndOfLine::Crlf),
+            _ => None,
+        },
+        insert_final_newline: property("insert_final_newline").and_then(parse_bool),
+        trim_trailing_whitespace: property("trim_trailing_whitespace").and_then(parse_bool),
+        ..PartialOptions::default()
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EditorConfigs;
+    use crate::temp_dir::TempDir;
+    use html_formatter::models::IndentStyle;
+
+    #[test]
+    fn nearer_files_and_later_sections_win() {
+        let dir = TempDir::new("editorconfig");
+        let root = dir.path();
+        let nested = root.join("project").join("emails");
+
+        dir.write(
+            ".editorconfig",
+            "[*]\nindent_style = tab\nmax_line_length = 120\n",
+        );
+        dir.write(
+            "project/.editorconfig",
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 2\n\n[emails/*.html]\nindent_size = 4\n\n[*.{htm,txt}]\nindent_size = unset\n",
+        );
+
+        let editorconfigs = EditorConfigs::new();
+
+        let layers = editorconfigs
+            .options_for(&nested.join("index.html"))
+            .unwrap();
+        // Parent of the root file is not read
+        assert_eq!(layers.len(), 1);
+        let (path, options) = &layers[0];
+        assert_eq!(path, &root.join("project").join(".editorconfig"));
+        assert_eq!(options.indent_style, Some(IndentStyle::Space));
+        assert_eq!(options.indent_size, Some(4));
+        assert_eq!(options.max_line_length, None);
+
+        let layers = editorconfigs
+            .options_for(&nested.join("index.htm"))
+            .unwrap();
+        assert_eq!(layers[0].1.indent_size, None);
+    }
+}
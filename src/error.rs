@@ -0,0 +1,49 @@
+use crate::tree::Divergence;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}:{})", self.message, self.line, self.column)
+    }
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    Parse(ParseError),
+    /// Formatted content doesn't build the same tree as the input
+    Unsafe(Box<Divergence>),
+    Write(fmt::Error),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(error) => write!(f, "parse error: {}", error),
+            Self::Unsafe(divergence) => {
+                write!(f, "formatting changes the document: {}", divergence)
+            }
+            Self::Write(error) => write!(f, "unable to write output: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<ParseError> for FormatError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl From<fmt::Error> for FormatError {
+    fn from(error: fmt::Error) -> Self {
+        Self::Write(error)
+    }
+}
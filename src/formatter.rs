@@ -1,56 +1,360 @@
-use crate::{config::Config, models::*};
+use crate::css::format_css;
+use crate::input_stream::InputStream;
+use crate::utils::{
+    count_line_breaks, is_preformatted_element, is_void_element, normalize_newlines,
+    quote_attribute_value,
+};
+use crate::{error::FormatError, lexer::Lexer, models::*, options::Options};
 use std::fmt::Write;
+use std::ops::Range;
 
 pub struct Formatter<'a> {
-    tokens: Vec<LexerToken>,
-    config: &'a Config,
+    lexer: Lexer<'a>,
+    options: &'a Options,
     indent_count: i32,
+    line_ending: &'static str,
+    /// Token read ahead while looking for the node after an ignore directive
+    pending_token: Option<LexerToken<'a>>,
+    /// Source offset of the end of the last written token, blank lines after it are kept
+    last_end: Option<usize>,
+    /// Last written token is a start tag, blank lines at the start of children are removed
+    after_open_tag: bool,
+    /// Line break after the last written line, the final one depends on `insert_final_newline`
+    pending_line_break: bool,
+    /// Last written token is a `<style>` start tag, the text after it is CSS
+    in_style: bool,
+}
+
+/// Comments which turn formatting off, e.g. `<!-- html-formatter-ignore -->`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Directive {
+    /// Copy the next node as is
+    Ignore,
+    /// Copy everything until `IgnoreEnd` as is
+    IgnoreStart,
+    IgnoreEnd,
+    /// Copy the whole file as is
+    IgnoreFile,
+}
+
+impl Directive {
+    fn parse(comment: &str) -> Option<Self> {
+        match comment.trim() {
+            "html-formatter-ignore" => Some(Self::Ignore),
+            "html-formatter-ignore-start" => Some(Self::IgnoreStart),
+            "html-formatter-ignore-end" => Some(Self::IgnoreEnd),
+            "html-formatter-ignore-file" => Some(Self::IgnoreFile),
+            _ => None,
+        }
+    }
+
+    fn of(token: &LexerToken<'_>) -> Option<Self> {
+        match token {
+            LexerToken::Comment(comment) => Self::parse(&comment.data),
+            _ => None,
+        }
+    }
 }
 
 impl<'a> Formatter<'a> {
-    pub fn new(tokens: Vec<LexerToken>, config: &'a Config) -> Self {
+    pub fn new(lexer: Lexer<'a>, options: &'a Options) -> Self {
+        let line_ending = options.end_of_line.resolve(lexer.source());
+
         Self {
-            tokens,
-            config,
+            lexer,
+            options,
             indent_count: 0,
+            line_ending,
+            pending_token: None,
+            last_end: None,
+            after_open_tag: false,
+            pending_line_break: false,
+            in_style: false,
         }
     }
 
-    pub fn format(&mut self) -> String {
+    pub fn format(&mut self) -> Result<String, FormatError> {
         let mut content: String = "".to_owned();
 
-        for token in self.tokens.iter().cloned() {
-            let result = match token.clone() {
+        self.format_to(&mut content)?;
+
+        Ok(content)
+    }
+
+    /// Formats tokens as soon as the lexer yields them, so output can be written
+    /// before the whole input is read
+    pub fn format_to(&mut self, content: &mut impl Write) -> Result<(), FormatError> {
+        if has_ignore_file_directive(self.lexer.source()) {
+            content.write_str(self.lexer.source())?;
+
+            return Ok(());
+        }
+
+        while let Some(token) = self.next_token() {
+            let opens_element = opens_element(&token);
+            let opens_style = opens_element
+                && matches!(&token, LexerToken::StartTag(tag) if tag.tag_name.eq_ignore_ascii_case("style"));
+            let opens_preformatted = opens_element
+                && matches!(&token, LexerToken::StartTag(tag) if is_preformatted_element(&tag.tag_name));
+            let is_end_tag = matches!(token, LexerToken::EndTag(_));
+            let directive = Directive::of(&token);
+            let Some(span) = content_span(self.lexer.source(), &token) else {
+                continue;
+            };
+
+            let result = match token {
                 LexerToken::StartTag(start_tag) => self.format_start_tag(start_tag),
-                LexerToken::Chars(chars) => self.format_chars(chars),
+                LexerToken::Chars(chars) if self.in_style => match self.format_style(chars) {
+                    Some(result) => result,
+                    None => continue,
+                },
+                LexerToken::Chars(chars) => match self.format_chars(chars) {
+                    Some(result) => result,
+                    None => continue,
+                },
                 LexerToken::EndTag(end_tag) => {
-                    self.indent_count -= 1;
+                    // Stray end tags don't move the indent below the root level
+                    self.indent_count = (self.indent_count - 1).max(0);
 
                     self.format_end_tag(end_tag)
                 }
+                LexerToken::Comment(comment) => self.format_comment(comment),
+                LexerToken::Doctype(doctype) => doctype.data.into_owned(),
             };
 
-            write!(
-                content,
-                "{}{}\n",
-                self.config.indent_style.repeat(self.indent_count),
-                result
-            )
-            .unwrap();
+            // Blank lines at the end of children are removed
+            if !is_end_tag {
+                self.write_blank_lines(content, span.start)?;
+            }
+            self.last_end = Some(span.end);
+            self.after_open_tag = opens_element;
+            self.in_style = opens_style;
+
+            match directive {
+                Some(Directive::Ignore) => {
+                    self.write_line(content, &result, true)?;
+                    self.copy_ignored_node(content)?;
+
+                    continue;
+                }
+                Some(Directive::IgnoreStart) => {
+                    // The copied region keeps its own line breaks
+                    self.write_line(content, &result, false)?;
+                    self.copy_ignored_region(content, span.end)?;
+
+                    continue;
+                }
+                _ if opens_preformatted => {
+                    self.write_line(content, &result, false)?;
+                    self.copy_preformatted(content, span.end)?;
+
+                    continue;
+                }
+                _ => self.write_line(content, &result, true)?,
+            }
 
-            if let LexerToken::StartTag(_) = token {
+            if opens_element {
                 self.indent_count += 1;
             }
         }
 
-        content
+        if self.pending_line_break && self.options.insert_final_newline {
+            content.write_str(self.line_ending)?;
+        }
+
+        match self.lexer.error() {
+            Some(error) => Err(error.clone().into()),
+            None => Ok(()),
+        }
+    }
+
+    fn next_token(&mut self) -> Option<LexerToken<'a>> {
+        self.pending_token
+            .take()
+            .or_else(|| self.lexer.next_token())
     }
 
-    fn format_start_tag(&self, tag: StartTag) -> String {
+    /// Keeps up to `max_blank_lines` blank lines found in the source between the last
+    /// written token and `start`, except at the start of file and of children
+    fn write_blank_lines(
+        &mut self,
+        content: &mut impl Write,
+        start: usize,
+    ) -> Result<(), FormatError> {
+        let Some(last_end) = self.last_end.filter(|_| !self.after_open_tag) else {
+            return Ok(());
+        };
+
+        let line_breaks = count_line_breaks(&self.lexer.source()[last_end..start]);
+        let blank_lines = line_breaks
+            .saturating_sub(1)
+            .min(self.options.max_blank_lines);
+
+        if blank_lines > 0 {
+            self.write_raw(content, &self.line_ending.repeat(blank_lines))?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes an indented line, tokens contain only LF line breaks, see
+    /// `utils::normalize_newlines`. `result` is written as is, only text lines are
+    /// trimmed by `format_chars`.
+    fn write_line(
+        &mut self,
+        content: &mut impl Write,
+        result: &str,
+        line_break: bool,
+    ) -> Result<(), FormatError> {
+        let mut line = format!("{}{}", self.options.indent(self.indent_count), result);
+
+        if self.line_ending != "\n" {
+            line = line.replace('\n', self.line_ending);
+        }
+
+        self.write_raw(content, &line)?;
+        self.pending_line_break = line_break;
+
+        Ok(())
+    }
+
+    /// Writes `text` as is after the line break of the previous line
+    fn write_raw(&mut self, content: &mut impl Write, text: &str) -> Result<(), FormatError> {
+        if self.pending_line_break {
+            content.write_str(self.line_ending)?;
+            self.pending_line_break = false;
+        }
+
+        content.write_str(text)?;
+
+        Ok(())
+    }
+
+    /// Writes copied source with its line breaks converted to `end_of_line`
+    fn write_source(&mut self, content: &mut impl Write, source: &str) -> Result<(), FormatError> {
+        let source = normalize_newlines(source);
+
+        if self.line_ending == "\n" {
+            self.write_raw(content, &source)
+        } else {
+            self.write_raw(content, &source.replace('\n', self.line_ending))
+        }
+    }
+
+    /// Copies the source of the node after `<!-- html-formatter-ignore -->`, that is
+    /// an element with its children and end tag, a text or a comment
+    fn copy_ignored_node(&mut self, content: &mut impl Write) -> Result<(), FormatError> {
+        let source = self.lexer.source();
+
+        let token = loop {
+            match self.next_token() {
+                Some(LexerToken::Chars(chars)) if chars.data.trim().is_empty() => {}
+                Some(token) => break token,
+                None => return Ok(()),
+            }
+        };
+
+        let Some(loc) = token.loc().cloned() else {
+            return Ok(());
+        };
+
+        let ignored = match &token {
+            // Directive at the end of an element has no node to ignore
+            LexerToken::EndTag(_) => {
+                self.pending_token = Some(token);
+
+                return Ok(());
+            }
+            LexerToken::Chars(_) => {
+                content_span(source, &token).unwrap_or(loc.start.offset..loc.end.offset)
+            }
+            _ if opens_element(&token) => loc.start.offset..self.skip_element(),
+            _ => loc.start.offset..loc.end.offset,
+        };
+        self.last_end = Some(ignored.end);
+
+        let indent = self.options.indent(self.indent_count);
+        self.write_raw(content, &indent)?;
+        self.write_source(content, &source[ignored])?;
+        self.pending_line_break = true;
+
+        Ok(())
+    }
+
+    /// Copies the children and the end tag of a `pre`, `textarea` or `script` element
+    /// from `start`, the end of its start tag, as their whitespace is rendered
+    fn copy_preformatted(
+        &mut self,
+        content: &mut impl Write,
+        start: usize,
+    ) -> Result<(), FormatError> {
+        let end = self.skip_element();
+
+        self.write_source(content, &self.lexer.source()[start..end])?;
+        self.pending_line_break = true;
+        self.last_end = Some(end);
+        self.after_open_tag = false;
+
+        Ok(())
+    }
+
+    /// Reads tokens to the end tag of the element opened by the last read token,
+    /// returns its end offset
+    fn skip_element(&mut self) -> usize {
+        let mut depth = 1;
+
+        while let Some(token) = self.next_token() {
+            if opens_element(&token) {
+                depth += 1;
+            } else if matches!(token, LexerToken::EndTag(_)) {
+                depth -= 1;
+            }
+
+            if depth == 0 {
+                return token
+                    .loc()
+                    .map_or(self.lexer.source().len(), |loc| loc.end.offset);
+            }
+        }
+
+        self.lexer.source().len()
+    }
+
+    /// Copies the source from `start` to the end of `<!-- html-formatter-ignore-end -->`
+    /// or to the end of file. Elements opened or closed in the region move the indent
+    /// of the nodes after it.
+    fn copy_ignored_region(
+        &mut self,
+        content: &mut impl Write,
+        start: usize,
+    ) -> Result<(), FormatError> {
+        let source = self.lexer.source();
+
+        while let Some(token) = self.next_token() {
+            if opens_element(&token) {
+                self.indent_count += 1;
+            } else if matches!(token, LexerToken::EndTag(_)) {
+                self.indent_count = (self.indent_count - 1).max(0);
+            }
+
+            if Directive::of(&token) == Some(Directive::IgnoreEnd) {
+                let end = token.loc().map_or(source.len(), |loc| loc.end.offset);
+                self.write_source(content, &source[start..end])?;
+                self.pending_line_break = true;
+                self.last_end = Some(end);
+
+                return Ok(());
+            }
+        }
+
+        self.write_source(content, &source[start..])
+    }
+
+    fn format_start_tag(&self, tag: StartTag<'_>) -> String {
         let mut result: String = "".to_owned();
 
-        let break_tag_attrs =
-            self.get_len_tag_attributes(&tag.attributes) > self.config.max_line_length as usize;
+        let break_tag_attrs = (self.options.single_attribute_per_line && tag.attributes.len() > 1)
+            || self.get_len_tag_attributes(&tag.attributes) > self.options.max_line_length as usize;
 
         let attrs = self.format_tag_attributes(tag.attributes, break_tag_attrs);
 
@@ -73,14 +377,18 @@ impl<'a> Formatter<'a> {
         result
     }
 
-    fn format_tag_attributes(&self, tag_attrs: Vec<TagAttribute>, break_tag_attrs: bool) -> String {
+    fn format_tag_attributes(
+        &self,
+        tag_attrs: Vec<TagAttribute<'_>>,
+        break_tag_attrs: bool,
+    ) -> String {
         tag_attrs
             .iter()
             .map(|tag_attr| {
                 if break_tag_attrs {
                     format!(
                         "{}{}",
-                        self.config.indent_style.repeat(self.indent_count + 1),
+                        self.options.indent(self.indent_count + 1),
                         self.format_tag_attribute(tag_attr)
                     )
                 } else {
@@ -91,7 +399,7 @@ impl<'a> Formatter<'a> {
             .join(if break_tag_attrs { "\n" } else { " " })
     }
 
-    fn get_len_tag_attributes(&self, tag_attrs: &Vec<TagAttribute>) -> usize {
+    fn get_len_tag_attributes(&self, tag_attrs: &[TagAttribute<'_>]) -> usize {
         let mut line_length: usize = 0;
 
         for tag_attr in tag_attrs.iter() {
@@ -101,18 +409,287 @@ impl<'a> Formatter<'a> {
         line_length
     }
 
-    fn format_tag_attribute(&self, tag_attr: &TagAttribute) -> String {
+    fn format_tag_attribute(&self, tag_attr: &TagAttribute<'_>) -> String {
         format!(
-            "{}=\"{}\"",
-            tag_attr.attribute_name, tag_attr.attribute_value
+            "{}={}",
+            tag_attr.attribute_name,
+            quote_attribute_value(&tag_attr.attribute_value)
         )
     }
 
-    fn format_chars(&self, chars: Chars) -> String {
-        chars.data
+    /// Text lines are indented to the current level, so formatting the output again
+    /// doesn't change it. Their ends are trimmed with `trim_trailing_whitespace`.
+    /// Whitespace only text is dropped, blank lines inside text are kept up to
+    /// `max_blank_lines`.
+    fn format_chars(&self, chars: Chars<'_>) -> Option<String> {
+        let indent = self.options.indent(self.indent_count);
+        let mut result = String::new();
+        let mut blank_lines = 0;
+
+        for line in chars.data.lines().map(str::trim_start) {
+            if line.is_empty() {
+                blank_lines += 1;
+                continue;
+            }
+
+            let line = if self.options.trim_trailing_whitespace {
+                line.trim_end()
+            } else {
+                line
+            };
+
+            if !result.is_empty() {
+                result.push('\n');
+                result.push_str(&"\n".repeat(blank_lines.min(self.options.max_blank_lines)));
+                result.push_str(&indent);
+            }
+
+            result.push_str(line);
+            blank_lines = 0;
+        }
+
+        (!result.is_empty()).then_some(result)
+    }
+
+    /// CSS of a `<style>` element, indented one level deeper than the tag
+    fn format_style(&self, chars: Chars<'_>) -> Option<String> {
+        let indent = self.options.indent(self.indent_count);
+        let css = format_css(&chars.data, self.options);
+
+        let lines: Vec<String> = css
+            .split('\n')
+            .enumerate()
+            .map(|(index, line)| {
+                if index == 0 || line.is_empty() {
+                    line.to_owned()
+                } else {
+                    format!("{}{}", indent, line)
+                }
+            })
+            .collect();
+
+        (!css.is_empty()).then(|| lines.join("\n"))
     }
 
-    fn format_end_tag(&self, end_tag: EndTag) -> String {
+    fn format_end_tag(&self, end_tag: EndTag<'_>) -> String {
         format!("</{}>", end_tag.tag_name)
     }
+
+    fn format_comment(&self, comment: Comment<'_>) -> String {
+        format!("<!--{}-->", comment.data)
+    }
+}
+
+/// Source range of the token without whitespace around text, `None` for whitespace only text
+fn content_span(source: &str, token: &LexerToken<'_>) -> Option<Range<usize>> {
+    let loc = token.loc()?;
+    let text = &source[loc.start.offset..loc.end.offset];
+
+    if !matches!(token, LexerToken::Chars(_)) {
+        return Some(loc.start.offset..loc.end.offset);
+    }
+
+    let start = loc.start.offset + text.len() - text.trim_start().len();
+    let end = loc.start.offset + text.trim_end().len();
+
+    (start < end).then_some(start..end)
+}
+
+fn opens_element(token: &LexerToken<'_>) -> bool {
+    match token {
+        LexerToken::StartTag(start_tag) => {
+            !start_tag.self_closing && !is_void_element(&start_tag.tag_name)
+        }
+        _ => false,
+    }
+}
+
+/// Lexes the source only if it may contain the directive
+fn has_ignore_file_directive(source: &str) -> bool {
+    source.contains("html-formatter-ignore-file")
+        && Lexer::new(InputStream::new(source))
+            .any(|token| Directive::of(&token) == Some(Directive::IgnoreFile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Formatter;
+    use crate::{input_stream::InputStream, lexer::Lexer, models::EndOfLine, options::Options};
+
+    const PREFORMATTED: &str = "<div>\n<pre>\n  a  \n\n    <b>b</b> c\n</pre>\n<textarea>  x\n    y  </textarea>\n<script>\nfunction f() {\n    if (a) {\n        return 1;\n    }\n}\n</script>\n</div>";
+
+    fn format(content: &str, options: &Options) -> String {
+        let stream = InputStream::new(content);
+        let lexer = Lexer::new(stream);
+
+        Formatter::new(lexer, options).format().unwrap()
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let options = Options::new().set_end_of_line(EndOfLine::Auto);
+
+        let content = format("<p title=\"a\r\nb\">\r\nHi\r\n</p>\r\n", &options);
+
+        assert!(content.contains("title=\"a\r\nb\""));
+        assert_eq!(content.matches('\r').count(), content.matches('\n').count());
+        assert!(!content.contains("\r\r") && !content.contains("\n\n"));
+    }
+
+    #[test]
+    fn copied_source_uses_end_of_line() {
+        let content = "<div>\r\n<!-- html-formatter-ignore -->\r\n<p>\r\n x\r\n</p>\r\n<pre>a\rb\r\n</pre>\r\n<!-- html-formatter-ignore-start -->\r\n<i>c</i>\r\n<!-- html-formatter-ignore-end -->\r\n</div>";
+
+        let lf = format(content, &Options::new());
+        assert!(!lf.contains('\r'));
+        assert!(lf.contains("<p>\n x\n</p>\n\t<pre>a\nb\n</pre>"));
+
+        let crlf = format(content, &Options::new().set_end_of_line(EndOfLine::Crlf));
+        assert_eq!(crlf.replace("\r\n", "\n"), lf);
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let options = Options::new().set_max_line_length(20);
+        let mut inputs = vec![
+            "<div>\n  <p class=\"a\">Hello\n   world</p>\n<br/>\n<img src=\"x\"><hr>\n</div>\n"
+                .to_owned(),
+            "<ul><li>a</li>  <li> b </li></ul>".to_owned(),
+            "</p>\n<p title=\"a long title\" class=\"a long class\">text</p>".to_owned(),
+            "<p title='a \"b\" c' alt='it&apos;s \"q\"' data-x=\"it's\">x</p>".to_owned(),
+            "<style>\n/* a\n * b */\na{color:red}\n\n\n@media print{p{margin:0}}</style>"
+                .to_owned(),
+            PREFORMATTED.to_owned(),
+        ];
+
+        let examples = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+        for entry in std::fs::read_dir(examples).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_file() {
+                inputs.push(std::fs::read_to_string(path).unwrap());
+            }
+        }
+
+        for input in inputs {
+            let formatted = format(&input, &options);
+
+            assert_eq!(
+                format(&formatted, &options),
+                formatted,
+                "input: {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn void_elements_keep_indent() {
+        let content = format("<div><br><img src=\"x\"/><p>a</p></div>", &Options::new());
+
+        assert_eq!(
+            content,
+            "<div>\n\t<br>\n\t<img src=\"x\"/>\n\t<p>\n\t\ta\n\t</p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn keeps_blank_lines_between_nodes() {
+        let content = format(
+            "\n\n<div>\n\n<p>a</p>\n\n\n\n<p>b\n\n\nc</p>\n\n</div>",
+            &Options::new(),
+        );
+
+        assert_eq!(
+            content,
+            "<div>\n\t<p>\n\t\ta\n\t</p>\n\n\t<p>\n\t\tb\n\n\t\tc\n\t</p>\n</div>\n"
+        );
+
+        let content = format("<p>a</p>\r\r<p>b</p>", &Options::new());
+        assert_eq!(content, "<p>\n\ta\n</p>\n\n<p>\n\tb\n</p>\n");
+    }
+
+    #[test]
+    fn formats_style_content_as_css() {
+        let content = format(
+            "<head>\n<style>\n  p > a{color:red}\n@media print { p { margin: 0 } }\n</style>\n<script>if (a < b) {}</script>\n</head>",
+            &Options::new(),
+        );
+
+        assert_eq!(
+            content,
+            "<head>\n\t<style>\n\t\tp > a {\n\t\t\tcolor: red;\n\t\t}\n\t\t@media print {\n\t\t\tp {\n\t\t\t\tmargin: 0;\n\t\t\t}\n\t\t}\n\t</style>\n\t<script>if (a < b) {}</script>\n</head>\n"
+        );
+    }
+
+    #[test]
+    fn keeps_preformatted_text() {
+        assert_eq!(
+            format(PREFORMATTED, &Options::new()),
+            "<div>\n\t<pre>\n  a  \n\n    <b>b</b> c\n</pre>\n\t<textarea>  x\n    y  </textarea>\n\t<script>\nfunction f() {\n    if (a) {\n        return 1;\n    }\n}\n</script>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn final_newline_and_trailing_whitespace() {
+        let content = "<p title=\"a  \nb\">\n\ta  \n\tb\n</p>\n<!-- c  \n d -->";
+
+        assert_eq!(
+            format(content, &Options::new()),
+            "<p title=\"a  \nb\">\n\ta\n\tb\n</p>\n<!-- c  \n d -->\n"
+        );
+
+        let options = Options::new()
+            .set_insert_final_newline(false)
+            .set_trim_trailing_whitespace(false);
+        assert_eq!(format(content, &options), content);
+    }
+
+    #[test]
+    fn ignore_directives_copy_source() {
+        let options = Options::new();
+
+        let content = format(
+            "<div>\n<!-- html-formatter-ignore -->\n<p>a  <b>b</b></p><i>c</i>\n<!--html-formatter-ignore-start-->\n  <u>d</u>\n  <!--html-formatter-ignore-end-->\n</div>",
+            &options,
+        );
+        assert_eq!(
+            content,
+            "<div>\n\t<!-- html-formatter-ignore -->\n\t<p>a  <b>b</b></p>\n\t<i>\n\t\tc\n\t</i>\n\t<!--html-formatter-ignore-start-->\n  <u>d</u>\n  <!--html-formatter-ignore-end-->\n</div>\n"
+        );
+
+        let content = format(
+            "<div>\n<div>\n<!-- html-formatter-ignore-start -->\n</div>\n<!-- html-formatter-ignore-end -->\n<p>a</p>\n</div>",
+            &options,
+        );
+        assert_eq!(
+            content,
+            "<div>\n\t<div>\n\t\t<!-- html-formatter-ignore-start -->\n</div>\n<!-- html-formatter-ignore-end -->\n\t<p>\n\t\ta\n\t</p>\n</div>\n"
+        );
+
+        let content = format(
+            "<!-- html-formatter-ignore -->\n\n\n\n\nx\n<p>a</p>",
+            &options,
+        );
+        assert_eq!(
+            content,
+            "<!-- html-formatter-ignore -->\nx\n<p>\n\ta\n</p>\n"
+        );
+
+        let ignored_file = "<!-- html-formatter-ignore-file -->\n<p>a</p>";
+        assert_eq!(format(ignored_file, &options), ignored_file);
+    }
+
+    #[test]
+    fn unterminated_tag_is_error() {
+        let stream = InputStream::new("<div>\n<p class=\"a");
+        let lexer = Lexer::new(stream);
+        let options = Options::new();
+
+        let error = Formatter::new(lexer, &options).format().unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "parse error: unexpected end of file inside of tag (2:0)"
+        );
+    }
 }
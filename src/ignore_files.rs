@@ -0,0 +1,201 @@
+use crate::cli_error::CliError;
+use crate::config::Config;
+use crate::dir_cache::DirCache;
+use ignore::gitignore::Gitignore;
+use ignore::Match;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const IGNORE_FILE_NAME: &str = ".htmlformatterignore";
+const GITIGNORE_FILE_NAME: &str = ".gitignore";
+
+/// Ignore rules with gitignore semantics. Matchers are ordered from the nearest
+/// to the farthest, so rules of nearer files override rules of farther ones.
+pub struct IgnoreFiles {
+    cwd: PathBuf,
+    /// Files of `cwd` and its parents
+    matchers: Vec<Gitignore>,
+    use_gitignore: bool,
+    /// `.gitignore` files of directories below `cwd`, loaded when paths inside them are checked
+    nested_gitignores: DirCache<Option<Arc<Gitignore>>>,
+}
+
+impl IgnoreFiles {
+    /// Uses `config.ignore_paths` if set, otherwise discovers `.htmlformatterignore`
+    /// files from `cwd` upward. `.gitignore` files are discovered up to the git root
+    /// and in every directory below `cwd`.
+    pub fn new(config: &Config, cwd: &Path) -> Result<Self, CliError> {
+        let mut matchers = vec![];
+
+        for ignore_path in &config.ignore_paths {
+            let ignore_path = cwd.join(ignore_path);
+
+            if !ignore_path.is_file() {
+                return Err(CliError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("ignore file {} not found", ignore_path.display()),
+                )));
+            }
+
+            matchers.push(load(&ignore_path)?);
+        }
+
+        let mut inside_git_repository = config.use_gitignore;
+
+        for dir in cwd.ancestors() {
+            if config.ignore_paths.is_empty() {
+                let ignore_path = dir.join(IGNORE_FILE_NAME);
+
+                if ignore_path.is_file() {
+                    matchers.push(load(&ignore_path)?);
+                }
+            }
+
+            if inside_git_repository {
+                let gitignore_path = dir.join(GITIGNORE_FILE_NAME);
+
+                if gitignore_path.is_file() {
+                    matchers.push(load(&gitignore_path)?);
+                }
+
+                inside_git_repository = !dir.join(".git").exists();
+            }
+        }
+
+        Ok(Self {
+            cwd: cwd.to_path_buf(),
+            matchers,
+            use_gitignore: config.use_gitignore,
+            nested_gitignores: DirCache::new(),
+        })
+    }
+
+    /// Path is ignored if it or any of its parent directories is matched
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = self.cwd.join(path);
+        let nested_gitignores = self.nested_gitignores(&path);
+        let matchers = nested_gitignores
+            .iter()
+            .map(Arc::as_ref)
+            .chain(&self.matchers);
+
+        for matcher in matchers {
+            if !path.starts_with(matcher.path()) {
+                continue;
+            }
+
+            match matcher.matched_path_or_any_parents(&path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        false
+    }
+
+    /// `.gitignore` files of the directories between `path` and `cwd`, nearest first.
+    /// Unreadable files are reported and skipped.
+    fn nested_gitignores(&self, path: &Path) -> Vec<Arc<Gitignore>> {
+        if !self.use_gitignore {
+            return vec![];
+        }
+
+        path.ancestors()
+            .skip(1)
+            .take_while(|dir| *dir != self.cwd && dir.starts_with(&self.cwd))
+            .filter_map(|dir| {
+                let gitignore = self.nested_gitignores.get_or_load(dir, || {
+                    let gitignore_path = dir.join(GITIGNORE_FILE_NAME);
+
+                    if !gitignore_path.is_file() {
+                        return Ok::<_, CliError>(None);
+                    }
+
+                    match load(&gitignore_path) {
+                        Ok(matcher) => Ok(Some(Arc::new(matcher))),
+                        Err(error) => {
+                            log::warn!("{}: {}", gitignore_path.display(), error);
+
+                            Ok(None)
+                        }
+                    }
+                });
+
+                gitignore.ok().flatten()
+            })
+            .collect()
+    }
+}
+
+fn load(path: &Path) -> Result<Gitignore, CliError> {
+    log::trace!("load ignore file {}", path.display());
+
+    let (matcher, error) = Gitignore::new(path);
+
+    match error {
+        Some(error) if matcher.is_empty() => Err(CliError::Ignore(error)),
+        Some(error) => {
+            log::warn!("{}", error);
+
+            Ok(matcher)
+        }
+        None => Ok(matcher),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IgnoreFiles;
+    use crate::config::Config;
+    use crate::temp_dir::TempDir;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn nearer_files_override_farther_ones() {
+        let dir = TempDir::new("ignore_files");
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        dir.write(
+            ".htmlformatterignore",
+            "dist/\n*.min.html\n!keep.min.html\n",
+        );
+        dir.write(".gitignore", "build/\n");
+        dir.write("sub/.gitignore", "vendor/\n!build/\n");
+
+        let config = Config::new(vec![]).set_ignore(vec![], true);
+        let ignore_files = IgnoreFiles::new(&config, dir.path()).unwrap();
+        let is_ignored = |path: &str| ignore_files.is_ignored(Path::new(path), false);
+
+        assert!(ignore_files.is_ignored(Path::new("dist"), true));
+        assert!(is_ignored("dist/index.html"));
+        assert!(is_ignored("a.min.html"));
+        assert!(!is_ignored("keep.min.html"));
+        assert!(is_ignored("build/a.html"));
+        assert!(is_ignored("sub/vendor/c.html"));
+        assert!(!is_ignored("sub/build/a.html"));
+        assert!(!is_ignored("vendor/c.html"));
+
+        let config = Config::new(vec![]);
+        let ignore_files = IgnoreFiles::new(&config, dir.path()).unwrap();
+        assert!(!ignore_files.is_ignored(Path::new("sub/vendor/c.html"), false));
+        assert!(ignore_files.is_ignored(Path::new("a.min.html"), false));
+    }
+
+    #[test]
+    fn ignore_paths_replace_discovered_files() {
+        let dir = TempDir::new("ignore_paths");
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        dir.write(".htmlformatterignore", "a.html\n");
+        dir.write("custom-ignore", "b.html\n");
+
+        let config = Config::new(vec![]).set_ignore(vec!["custom-ignore".to_owned()], false);
+        let ignore_files = IgnoreFiles::new(&config, dir.path()).unwrap();
+        assert!(!ignore_files.is_ignored(Path::new("a.html"), false));
+        assert!(ignore_files.is_ignored(Path::new("b.html"), false));
+
+        let config = Config::new(vec![]).set_ignore(vec!["missing".to_owned()], false);
+        let error = IgnoreFiles::new(&config, dir.path()).err().unwrap();
+        assert!(error.to_string().starts_with("ignore file "));
+    }
+}
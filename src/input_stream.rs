@@ -1,8 +1,14 @@
+/// Cursor over the input, fields are read through getters so `pos` always
+/// stays on a char boundary
 pub struct InputStream<'a> {
+    /// Byte offset of the next char in `input`
     pos: usize,
-    pub line: usize,
-    pub col: usize,
-    pub input: &'a str,
+    line: usize,
+    /// Column counted in chars (Unicode scalar values)
+    col: usize,
+    /// Column counted in UTF-16 code units, as editors (LSP) expect it
+    col_utf16: usize,
+    input: &'a str,
 }
 
 impl<'a> InputStream<'a> {
@@ -11,33 +17,93 @@ impl<'a> InputStream<'a> {
             pos: 0,
             line: 1,
             col: 0,
+            col_utf16: 0,
             input,
         }
     }
 
-    pub fn next(&mut self) -> char {
-        let char = self.input.chars().nth(self.pos).unwrap();
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
 
-        self.pos += 1;
-        if char == '\n' {
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    pub fn col_utf16(&self) -> usize {
+        self.col_utf16
+    }
+
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    pub(crate) fn next_char(&mut self) -> char {
+        let char = self.peek();
+
+        self.pos += char.len_utf8();
+        if char == '\r' && self.input[self.pos..].starts_with('\n') {
+            // CRLF is counted as a single line break by the following '\n'
+        } else if char == '\n' || char == '\r' {
             self.line += 1;
             self.col = 0;
+            self.col_utf16 = 0;
         } else {
             self.col += 1;
+            self.col_utf16 += char.len_utf16();
         }
 
         char
     }
 
-    pub fn peek(&self) -> char {
-        self.input.chars().nth(self.pos).unwrap()
+    pub(crate) fn peek(&self) -> char {
+        self.input[self.pos..].chars().next().unwrap()
+    }
+
+    pub fn starts_with(&self, pattern: &str) -> bool {
+        self.input[self.pos..].starts_with(pattern)
+    }
+
+    pub fn starts_with_ignore_case(&self, pattern: &str) -> bool {
+        self.input[self.pos..]
+            .get(..pattern.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(pattern))
     }
 
     pub fn eof(&self) -> bool {
         self.pos >= self.input.len()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputStream;
+
+    #[test]
+    fn multibyte_input() {
+        let mut stream = InputStream::new("ä😀\nb");
+
+        assert_eq!(stream.next_char(), 'ä');
+        assert_eq!(stream.next_char(), '😀');
+        assert_eq!((stream.pos, stream.col, stream.col_utf16), (6, 2, 3));
+        assert_eq!(stream.next_char(), '\n');
+        assert_eq!(stream.next_char(), 'b');
+        assert_eq!((stream.line, stream.col), (2, 1));
+        assert!(stream.eof());
+    }
+
+    #[test]
+    fn crlf_is_single_line_break() {
+        let mut stream = InputStream::new("a\r\nb\rc");
+
+        while !stream.eof() {
+            stream.next_char();
+        }
 
-    pub fn croak(&self, msg: &str) {
-        panic!("{} ({}:{})", msg, self.line, self.col);
+        assert_eq!((stream.line, stream.col), (3, 1));
     }
 }
@@ -1,12 +1,21 @@
+use crate::error::ParseError;
 use crate::input_stream::InputStream;
 use crate::models::*;
 use crate::utils;
+use std::borrow::Cow;
+
+/// Elements whose content is text up to their end tag, e.g. `<` in a script isn't a tag
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
 
 #[derive(Eq, Hash, PartialEq)]
 enum LexerState {
     BeforeData,
     Chars,
+    RawText,
     TagOpen,
+    MarkupDeclarationOpen,
+    Comment,
+    Doctype,
     TagName,
     EndTagOpen,
     EndTagName,
@@ -24,10 +33,19 @@ enum LexerState {
 pub struct Lexer<'a> {
     input_stream: InputStream<'a>,
     state: LexerState,
-    tokens: Vec<LexerToken>,
-    start_line: usize,
-    start_col: usize,
-    current_attribute: Option<TagAttribute>,
+    /// Token which is being read right now
+    current_token: Option<LexerToken<'a>>,
+    /// Token which is read completely and waits to be yielded
+    completed_token: Option<LexerToken<'a>>,
+    start_pos: LocationPos,
+    tag_name_start: usize,
+    /// Offset of the comment text after `<!--` or of the doctype `<`, junk before
+    /// `<!` may precede `start_pos`
+    data_start: usize,
+    current_attribute: Option<TagAttribute<'a>>,
+    /// End tag which finishes raw text after the start tag of a raw text element
+    raw_text_end: Option<String>,
+    error: Option<ParseError>,
 }
 
 impl<'a> Lexer<'a> {
@@ -35,46 +53,92 @@ impl<'a> Lexer<'a> {
         Self {
             input_stream,
             state: LexerState::BeforeData,
-            tokens: Vec::new(),
-            start_col: 0,
-            start_line: 1,
+            current_token: None,
+            completed_token: None,
+            start_pos: LocationPos {
+                line: 1,
+                column: 0,
+                offset: 0,
+            },
+            tag_name_start: 0,
+            data_start: 0,
             current_attribute: None,
+            raw_text_end: None,
+            error: None,
         }
     }
 
-    pub fn tokenize(&mut self) -> &Vec<LexerToken> {
-        while !self.input_stream.eof() {
+    /// Reads input until the next token is complete
+    pub fn next_token(&mut self) -> Option<LexerToken<'a>> {
+        while self.completed_token.is_none() && !self.input_stream.eof() {
             self.read_next();
         }
 
-        &self.tokens
+        if self.completed_token.is_none() && self.input_stream.eof() {
+            self.finish_eof();
+        }
+
+        self.completed_token.take()
     }
 
-    fn read_next(&mut self) {
-        // self.read_while(utils::is_whitespace);
+    /// Error which stopped reading, available after the iterator is exhausted
+    pub fn error(&self) -> Option<&ParseError> {
+        self.error.as_ref()
+    }
 
-        if self.input_stream.eof() {
-            // end of file
-            println!("End of reading");
-        } else {
-            match self.state {
-                LexerState::BeforeData => self.read_before_data(),
-                LexerState::Chars => self.read_chars(),
-                LexerState::TagOpen => self.read_tag_open(),
-                LexerState::TagName => self.read_tag_name(),
-                LexerState::EndTagOpen => self.read_end_tag_open(),
-                LexerState::EndTagName => self.read_end_tag_name(),
-                LexerState::BeforeAttributeName => self.read_before_attribute_name(),
-                LexerState::AttributeName => self.read_attribute_name(),
-                LexerState::AfterAttributeName => self.read_after_attribute_name(),
-                LexerState::BeforeAttributeValue => self.read_before_attribute_value(),
-                LexerState::AttributeValueDoubleQuoted => self.read_attribute_value_double_quoted(),
-                LexerState::AttributeValueSingleQuoted => self.read_attribute_value_single_quoted(),
-                LexerState::AttributeValueUnquoted => self.read_attribute_value_unquoted(),
-                LexerState::AfterAttributeValueQuoted => self.read_after_attribute_value_quoted(),
-                LexerState::SelfClosingStartTag => self.read_self_closing_start_tag(),
+    fn finish_eof(&mut self) {
+        if self.state == LexerState::Chars || self.state == LexerState::RawText {
+            self.finish_chars();
+        } else if self.state != LexerState::BeforeData {
+            let inside = match self.state {
+                LexerState::Comment => "comment",
+                LexerState::Doctype => "doctype",
+                _ => "tag",
             };
+
+            self.error = Some(ParseError {
+                message: format!("unexpected end of file inside of {}", inside),
+                line: self.start_pos.line,
+                column: self.start_pos.column,
+            });
+            self.current_token = None;
+            self.current_attribute = None;
         }
+
+        self.transition_to(LexerState::BeforeData);
+    }
+
+    fn warn(&self, message: &str) {
+        log::warn!(
+            "syntax error \"{}\" ({}:{})",
+            message,
+            self.input_stream.line(),
+            self.input_stream.col()
+        );
+    }
+
+    fn read_next(&mut self) {
+        match self.state {
+            LexerState::BeforeData => self.read_before_data(),
+            LexerState::Chars => self.read_chars(),
+            LexerState::RawText => self.read_raw_text(),
+            LexerState::TagOpen => self.read_tag_open(),
+            LexerState::MarkupDeclarationOpen => self.read_markup_declaration_open(),
+            LexerState::Comment => self.read_comment(),
+            LexerState::Doctype => self.read_doctype(),
+            LexerState::TagName => self.read_tag_name(),
+            LexerState::EndTagOpen => self.read_end_tag_open(),
+            LexerState::EndTagName => self.read_end_tag_name(),
+            LexerState::BeforeAttributeName => self.read_before_attribute_name(),
+            LexerState::AttributeName => self.read_attribute_name(),
+            LexerState::AfterAttributeName => self.read_after_attribute_name(),
+            LexerState::BeforeAttributeValue => self.read_before_attribute_value(),
+            LexerState::AttributeValueDoubleQuoted => self.read_attribute_value_double_quoted(),
+            LexerState::AttributeValueSingleQuoted => self.read_attribute_value_single_quoted(),
+            LexerState::AttributeValueUnquoted => self.read_attribute_value_unquoted(),
+            LexerState::AfterAttributeValueQuoted => self.read_after_attribute_value_quoted(),
+            LexerState::SelfClosingStartTag => self.read_self_closing_start_tag(),
+        };
     }
 
     fn transition_to(&mut self, state: LexerState) {
@@ -82,68 +146,93 @@ impl<'a> Lexer<'a> {
     }
 
     fn consume(&mut self) -> char {
-        self.input_stream.next()
+        self.input_stream.next_char()
     }
 
-    fn read_while(&mut self, predicate: fn(char) -> bool) -> String {
-        let mut str: String = "".to_string();
+    fn begin_token(&mut self, token: LexerToken<'a>) {
+        self.current_token = Some(token);
+    }
 
-        while !self.input_stream.eof() && predicate(self.input_stream.peek()) {
-            str.push(self.input_stream.next());
-        }
+    fn complete_token(&mut self) {
+        self.completed_token = self.current_token.take();
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.input_stream.input()
+    }
+
+    fn slice(&self, start: usize, end: usize) -> Cow<'a, str> {
+        let source: &'a str = self.input_stream.input();
 
-        str
+        utils::normalize_newlines(&source[start..end])
     }
 
-    fn push(&mut self, token: LexerToken) {
-        self.tokens.push(token);
+    fn current_pos(&self) -> LocationPos {
+        LocationPos {
+            line: self.input_stream.line(),
+            column: self.input_stream.col(),
+            offset: self.input_stream.pos(),
+        }
     }
 
     fn retrieve_loc_info(&mut self) -> Location {
-        let loc = Location {
-            start: LocationPos {
-                line: self.start_line,
-                column: self.start_col,
-            },
-            end: LocationPos {
-                line: self.input_stream.line,
-                column: self.input_stream.col,
-            },
-        };
-
-        self.start_line = self.input_stream.line;
-        self.start_col = self.input_stream.col;
+        let end = self.current_pos();
+        let start = std::mem::replace(&mut self.start_pos, end.clone());
 
-        loc
+        Location { start, end }
     }
 
     fn begin_chars(&mut self) {
-        self.push(LexerToken::Chars(Chars {
-            data: "".to_owned(),
+        self.begin_token(LexerToken::Chars(Chars {
+            data: Cow::Borrowed(""),
             loc: None,
         }));
     }
 
-    fn append_to_chars(&mut self, char: char) {
-        let token = self.tokens.last_mut();
+    fn finish_chars(&mut self) {
+        let loc = self.retrieve_loc_info();
+        let data = self.slice(loc.start.offset, loc.end.offset);
+        let token = self.current_token.as_mut();
 
         if let Some(LexerToken::Chars(chars)) = token {
-            chars.append_to_chars(char);
+            chars.data = data;
+            chars.add_loc(loc)
         }
+
+        self.complete_token();
     }
 
-    fn finish_chars(&mut self) {
+    fn finish_comment(&mut self) {
         let loc = self.retrieve_loc_info();
-        let token = self.tokens.last_mut();
+        // Without `-->`
+        let data = self.slice(self.data_start, loc.end.offset - 3);
+        let token = self.current_token.as_mut();
 
-        if let Some(LexerToken::Chars(chars)) = token {
-            chars.add_loc(loc)
+        if let Some(LexerToken::Comment(comment)) = token {
+            comment.data = data;
+            comment.add_loc(loc);
         }
+
+        self.complete_token();
+    }
+
+    fn finish_doctype(&mut self) {
+        let loc = self.retrieve_loc_info();
+        let data = self.slice(self.data_start, loc.end.offset);
+        let token = self.current_token.as_mut();
+
+        if let Some(LexerToken::Doctype(doctype)) = token {
+            doctype.data = data;
+            doctype.add_loc(loc);
+        }
+
+        self.complete_token();
     }
 
     fn begin_start_tag(&mut self) {
-        self.push(LexerToken::StartTag(StartTag {
-            tag_name: "".to_owned(),
+        self.tag_name_start = self.input_stream.pos();
+        self.begin_token(LexerToken::StartTag(StartTag {
+            tag_name: Cow::Borrowed(""),
             attributes: vec![],
             self_closing: false,
             loc: None,
@@ -151,24 +240,26 @@ impl<'a> Lexer<'a> {
     }
 
     fn begin_end_tag(&mut self) {
-        self.push(LexerToken::EndTag(EndTag {
-            tag_name: "".to_owned(),
+        self.tag_name_start = self.input_stream.pos();
+        self.begin_token(LexerToken::EndTag(EndTag {
+            tag_name: Cow::Borrowed(""),
             loc: None,
         }))
     }
 
-    fn append_to_tag_name(&mut self, char: char) {
-        let token = self.tokens.last_mut();
+    fn extend_tag_name(&mut self) {
+        let tag_name = self.slice(self.tag_name_start, self.input_stream.pos());
+        let token = self.current_token.as_mut();
 
         if let Some(LexerToken::StartTag(tag)) = token {
-            tag.append_to_tag_name(char);
+            tag.tag_name = tag_name;
         } else if let Some(LexerToken::EndTag(tag)) = token {
-            tag.append_to_tag_name(char);
+            tag.tag_name = tag_name;
         }
     }
 
     fn mark_tag_as_self_closing(&mut self) {
-        let token = self.tokens.last_mut();
+        let token = self.current_token.as_mut();
 
         if let Some(LexerToken::StartTag(tag)) = token {
             tag.mark_as_self_closing();
@@ -177,39 +268,86 @@ impl<'a> Lexer<'a> {
 
     fn finish_tag(&mut self) {
         let loc = self.retrieve_loc_info();
-        let token = self.tokens.last_mut();
+        let token = self.current_token.as_mut();
 
         if let Some(LexerToken::StartTag(tag)) = token {
+            let is_raw_text = RAW_TEXT_ELEMENTS
+                .iter()
+                .any(|name| tag.tag_name.eq_ignore_ascii_case(name));
+
+            if is_raw_text && !tag.self_closing {
+                self.raw_text_end = Some(format!("</{}", tag.tag_name));
+            }
+
             tag.add_loc(loc);
         } else if let Some(LexerToken::EndTag(tag)) = token {
             tag.add_loc(loc);
         }
+
+        self.complete_token();
     }
 
     fn begin_attribute(&mut self) {
+        let pos = self.current_pos();
+
         self.current_attribute = Some(TagAttribute {
-            attribute_name: "".to_owned(),
-            attribute_value: "".to_owned(),
+            attribute_name: Cow::Borrowed(""),
+            attribute_value: Cow::Borrowed(""),
+            name_loc: Some(Location {
+                start: pos.clone(),
+                end: pos,
+            }),
+            value_loc: None,
         })
     }
 
-    fn append_to_attribute_name(&mut self, char: char) {
+    fn extend_attribute_name(&mut self) {
+        let source: &'a str = self.input_stream.input();
+        let pos = self.current_pos();
+
         if let Some(attribute) = self.current_attribute.as_mut() {
-            attribute.append_to_attribute_name(char);
+            if let Some(loc) = attribute.name_loc.as_mut() {
+                attribute.attribute_name = Cow::Borrowed(&source[loc.start.offset..pos.offset]);
+                loc.end = pos;
+            }
         }
     }
 
-    fn append_to_attribute_value(&mut self, char: char) {
+    fn begin_attribute_value(&mut self) {
+        let pos = self.current_pos();
+
         if let Some(attribute) = self.current_attribute.as_mut() {
-            attribute.append_to_attribute_value(char);
+            attribute.value_loc = Some(Location {
+                start: pos.clone(),
+                end: pos,
+            });
+        }
+    }
+
+    fn extend_attribute_value(&mut self) {
+        let pos = self.current_pos();
+
+        if let Some(loc) = self
+            .current_attribute
+            .as_mut()
+            .and_then(|attribute| attribute.value_loc.as_mut())
+        {
+            loc.end = pos;
         }
     }
 
+    /// Value is sliced once here, slicing it on every char would make long values quadratic
     fn finish_attribute_value(&mut self) {
-        let token = self.tokens.last_mut();
+        let Some(mut attribute) = self.current_attribute.take() else {
+            return;
+        };
 
-        if let Some(LexerToken::StartTag(tag)) = token {
-            tag.append_to_attributes(self.current_attribute.take().unwrap());
+        if let Some(loc) = &attribute.value_loc {
+            attribute.attribute_value = self.slice(loc.start.offset, loc.end.offset);
+        }
+
+        if let Some(LexerToken::StartTag(tag)) = self.current_token.as_mut() {
+            tag.append_to_attributes(attribute);
         }
     }
 
@@ -218,7 +356,10 @@ impl<'a> Lexer<'a> {
     fn read_before_data(&mut self) {
         let char = self.input_stream.peek();
 
-        if char == '<' {
+        if self.raw_text_end.is_some() {
+            self.transition_to(LexerState::RawText);
+            self.begin_chars();
+        } else if char == '<' {
             self.transition_to(LexerState::TagOpen);
             self.consume();
         } else {
@@ -236,19 +377,84 @@ impl<'a> Lexer<'a> {
             self.consume();
         } else {
             self.consume();
-            self.append_to_chars(char);
         }
     }
 
+    fn read_raw_text(&mut self) {
+        let is_end = self
+            .raw_text_end
+            .as_ref()
+            .is_some_and(|end_tag| self.input_stream.starts_with_ignore_case(end_tag));
+
+        if is_end {
+            self.raw_text_end = None;
+            self.finish_chars();
+            self.transition_to(LexerState::TagOpen);
+        }
+
+        self.consume();
+    }
+
     fn read_tag_open(&mut self) {
-        let char = self.consume();
+        let char = self.input_stream.peek();
 
         if char == '/' {
+            self.consume();
             self.transition_to(LexerState::EndTagOpen)
+        } else if char == '!' {
+            self.consume();
+            self.transition_to(LexerState::MarkupDeclarationOpen)
         } else if utils::is_alphabet(char) {
             self.transition_to(LexerState::TagName);
             self.begin_start_tag();
-            self.append_to_tag_name(char);
+            self.consume();
+            self.extend_tag_name();
+        } else {
+            self.consume();
+        }
+    }
+
+    fn read_markup_declaration_open(&mut self) {
+        if self.input_stream.starts_with("--") {
+            self.consume();
+            self.consume();
+            self.data_start = self.input_stream.pos();
+            self.begin_token(LexerToken::Comment(Comment {
+                data: Cow::Borrowed(""),
+                loc: None,
+            }));
+            self.transition_to(LexerState::Comment);
+        } else if self.input_stream.starts_with_ignore_case("doctype") {
+            // Before `<!`
+            self.data_start = self.input_stream.pos() - 2;
+            self.begin_token(LexerToken::Doctype(Doctype {
+                data: Cow::Borrowed(""),
+                loc: None,
+            }));
+            self.transition_to(LexerState::Doctype);
+        } else {
+            self.transition_to(LexerState::TagOpen);
+        }
+    }
+
+    fn read_doctype(&mut self) {
+        let char = self.consume();
+
+        if char == '>' {
+            self.finish_doctype();
+            self.transition_to(LexerState::BeforeData);
+        }
+    }
+
+    fn read_comment(&mut self) {
+        if self.input_stream.starts_with("-->") {
+            self.consume();
+            self.consume();
+            self.consume();
+            self.finish_comment();
+            self.transition_to(LexerState::BeforeData);
+        } else {
+            self.consume();
         }
     }
 
@@ -263,17 +469,20 @@ impl<'a> Lexer<'a> {
             self.finish_tag();
             self.transition_to(LexerState::BeforeData);
         } else {
-            self.append_to_tag_name(char);
+            self.extend_tag_name();
         }
     }
 
     fn read_end_tag_open(&mut self) {
-        let char = self.consume();
+        let char = self.input_stream.peek();
 
         if utils::is_alphabet(char) {
             self.transition_to(LexerState::EndTagName);
             self.begin_end_tag();
-            self.append_to_tag_name(char);
+            self.consume();
+            self.extend_tag_name();
+        } else {
+            self.consume();
         }
     }
 
@@ -288,7 +497,7 @@ impl<'a> Lexer<'a> {
             self.finish_tag();
             self.transition_to(LexerState::BeforeData);
         } else {
-            self.append_to_tag_name(char);
+            self.extend_tag_name();
         }
     }
 
@@ -298,12 +507,14 @@ impl<'a> Lexer<'a> {
         if char.is_whitespace() {
             self.consume();
         } else if char == '/' {
+            self.consume();
             self.transition_to(LexerState::SelfClosingStartTag);
         } else if char == '>' {
+            self.consume();
             self.finish_tag();
             self.transition_to(LexerState::BeforeData);
         } else if char == '=' {
-            println!("syntax error \"attribute name can't start with equals sign\"");
+            self.warn("attribute name can't start with equals sign");
             self.transition_to(LexerState::AttributeName);
             self.begin_attribute();
         } else {
@@ -331,12 +542,12 @@ impl<'a> Lexer<'a> {
             self.finish_tag();
             self.transition_to(LexerState::BeforeData);
         } else if char == '"' || char == '\'' || char == '<' {
-            println!("syntax error \"invalid char in attribute name\"");
+            self.warn("invalid char in attribute name");
             self.consume();
-            self.append_to_attribute_name(char);
+            self.extend_attribute_name();
         } else {
             self.consume();
-            self.append_to_attribute_name(char);
+            self.extend_attribute_name();
         }
     }
 
@@ -366,7 +577,7 @@ impl<'a> Lexer<'a> {
             self.transition_to(LexerState::AttributeName);
             self.begin_attribute();
             self.consume();
-            self.append_to_attribute_name(char);
+            self.extend_attribute_name();
         }
     }
 
@@ -378,9 +589,11 @@ impl<'a> Lexer<'a> {
         } else if char == '"' {
             self.transition_to(LexerState::AttributeValueDoubleQuoted);
             self.consume();
+            self.begin_attribute_value();
         } else if char == '\'' {
             self.transition_to(LexerState::AttributeValueSingleQuoted);
             self.consume();
+            self.begin_attribute_value();
         } else if char == '>' {
             self.finish_attribute_value();
             self.consume();
@@ -388,30 +601,35 @@ impl<'a> Lexer<'a> {
             self.transition_to(LexerState::BeforeData);
         } else {
             self.transition_to(LexerState::AttributeValueUnquoted);
+            self.begin_attribute_value();
             self.consume();
-            self.append_to_attribute_value(char);
+            self.extend_attribute_value();
         }
     }
 
     fn read_attribute_value_double_quoted(&mut self) {
-        let char = self.consume();
+        let char = self.input_stream.peek();
 
         if char == '"' {
             self.finish_attribute_value();
+            self.consume();
             self.transition_to(LexerState::AfterAttributeValueQuoted);
         } else {
-            self.append_to_attribute_value(char);
+            self.consume();
+            self.extend_attribute_value();
         }
     }
 
     fn read_attribute_value_single_quoted(&mut self) {
-        let char = self.consume();
+        let char = self.input_stream.peek();
 
         if char == '\'' {
             self.finish_attribute_value();
+            self.consume();
             self.transition_to(LexerState::AfterAttributeValueQuoted);
         } else {
-            self.append_to_attribute_value(char);
+            self.consume();
+            self.extend_attribute_value();
         }
     }
 
@@ -430,7 +648,7 @@ impl<'a> Lexer<'a> {
                 self.transition_to(LexerState::SelfClosingStartTag);
             } else {
                 // In example: <a href=https://www.w3schools.com>
-                self.append_to_attribute_value(char);
+                self.extend_attribute_value();
             }
         } else if char == '>' {
             self.finish_attribute_value();
@@ -439,7 +657,7 @@ impl<'a> Lexer<'a> {
             self.transition_to(LexerState::BeforeData);
         } else {
             self.consume();
-            self.append_to_attribute_value(char);
+            self.extend_attribute_value();
         }
     }
 
@@ -475,10 +693,19 @@ impl<'a> Lexer<'a> {
     }
 }
 
+impl<'a> Iterator for Lexer<'a> {
+    type Item = LexerToken<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Lexer;
     use crate::input_stream::InputStream;
+    use crate::models::LexerToken;
 
     #[test]
     fn attribute_without_quotes() {
@@ -486,9 +713,104 @@ mod tests {
             "<html><body><a href=https://www.w3schools.com>This is a link</a></body></html>";
 
         let stream = InputStream::new(content);
-        let mut lexer = Lexer::new(stream);
-        let tokens = lexer.tokenize();
+        let tokens: Vec<_> = Lexer::new(stream).collect();
 
         insta::assert_debug_snapshot!(tokens);
     }
+
+    #[test]
+    fn spans_slice_source() {
+        let content = "<p id=\"ä\" hidden>Grüße</p>";
+
+        let stream = InputStream::new(content);
+        let tokens: Vec<_> = Lexer::new(stream).collect();
+
+        let slices: Vec<&str> = tokens
+            .iter()
+            .map(|token| {
+                let loc = token.loc().unwrap();
+
+                &content[loc.start.offset..loc.end.offset]
+            })
+            .collect();
+        assert_eq!(slices, ["<p id=\"ä\" hidden>", "Grüße", "</p>"]);
+
+        let LexerToken::StartTag(tag) = &tokens[0] else {
+            panic!("expected start tag");
+        };
+        let id = &tag.attributes[0];
+        let name_loc = id.name_loc.as_ref().unwrap();
+        let value_loc = id.value_loc.as_ref().unwrap();
+        assert_eq!(&content[name_loc.start.offset..name_loc.end.offset], "id");
+        assert_eq!(&content[value_loc.start.offset..value_loc.end.offset], "ä");
+        assert_eq!(tag.attributes[1].value_loc, None);
+    }
+
+    #[test]
+    fn comments() {
+        let content = "<!---->a<!-- <p>b</p> -->";
+
+        let stream = InputStream::new(content);
+        let tokens: Vec<_> = Lexer::new(stream).collect();
+
+        let LexerToken::Comment(comment) = &tokens[2] else {
+            panic!("expected comment");
+        };
+        assert_eq!(comment.data, " <p>b</p> ");
+        assert_eq!(tokens.len(), 3);
+        assert!(matches!(&tokens[0], LexerToken::Comment(comment) if comment.data.is_empty()));
+
+        for content in ["< <!--x-->", "<😀<!--x-->"] {
+            let stream = InputStream::new(content);
+            let tokens: Vec<_> = Lexer::new(stream).collect();
+
+            assert!(matches!(&tokens[0], LexerToken::Comment(comment) if comment.data == "x"));
+        }
+    }
+
+    #[test]
+    fn doctype() {
+        let content = "<!doctype html>\n<html>";
+
+        let stream = InputStream::new(content);
+        let tokens: Vec<_> = Lexer::new(stream).collect();
+
+        assert!(
+            matches!(&tokens[0], LexerToken::Doctype(doctype) if doctype.data == "<!doctype html>")
+        );
+        assert_eq!(tokens.len(), 3);
+    }
+
+    #[test]
+    fn raw_text_elements() {
+        let content = "<script>a < b && '</p>'</SCRIPT><style></style>";
+
+        let stream = InputStream::new(content);
+        let tokens: Vec<_> = Lexer::new(stream).collect();
+
+        let LexerToken::Chars(chars) = &tokens[1] else {
+            panic!("expected chars");
+        };
+        assert_eq!(chars.data, "a < b && '</p>'");
+        assert!(matches!(&tokens[2], LexerToken::EndTag(tag) if tag.tag_name == "SCRIPT"));
+        assert_eq!(tokens.len(), 6);
+    }
+
+    #[test]
+    fn yields_tokens_as_they_complete() {
+        let content = "<div>text</div>";
+
+        let stream = InputStream::new(content);
+        let mut lexer = Lexer::new(stream);
+
+        let Some(LexerToken::StartTag(tag)) = lexer.next_token() else {
+            panic!("expected start tag");
+        };
+        assert_eq!(tag.tag_name, "div");
+        assert_eq!(lexer.input_stream.pos(), 5);
+
+        assert!(matches!(lexer.next_token(), Some(LexerToken::Chars(_))));
+        assert!(matches!(lexer.next_token(), Some(LexerToken::EndTag(_))));
+        assert_eq!(lexer.next_token(), None);
+    }
 }
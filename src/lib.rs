@@ -0,0 +1,40 @@
+//! HTML formatter library, the `html-formatter` binary is built on top of it.
+//!
+//! ```
+//! use html_formatter::{format_str, IndentStyle, Options};
+//!
+//! let options = Options::new().set_indent_style(IndentStyle::Space);
+//! let formatted = format_str("<div><p>text</p></div>", &options).unwrap();
+//!
+//! assert_eq!(formatted, "<div>\n  <p>\n    text\n  </p>\n</div>\n");
+//! ```
+
+pub mod css;
+pub mod error;
+pub mod formatter;
+pub mod input_stream;
+pub mod lexer;
+pub mod minify;
+pub mod models;
+pub mod options;
+pub mod range;
+pub mod tree;
+mod utils;
+
+pub use css::format_css;
+pub use error::{FormatError, ParseError};
+pub use formatter::Formatter;
+pub use input_stream::InputStream;
+pub use lexer::Lexer;
+pub use minify::{minify_str, MinifyOptions};
+pub use models::{EndOfLine, IndentStyle, LexerToken};
+pub use options::{Options, PartialOptions};
+pub use range::format_range;
+pub use tree::{check_equivalent, parse, Node};
+
+/// Formats `content` with `options`
+pub fn format_str(content: &str, options: &Options) -> Result<String, FormatError> {
+    let lexer = Lexer::new(InputStream::new(content));
+
+    Formatter::new(lexer, options).format()
+}
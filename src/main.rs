@@ -1,34 +1,115 @@
 mod args;
+mod cli_error;
 mod config;
-mod formatter;
-mod input_stream;
-mod lexer;
-mod models;
-mod utils;
+mod config_file;
+mod diff;
+mod dir_cache;
+mod editorconfig;
+mod ignore_files;
+#[cfg(test)]
+mod temp_dir;
 mod walker;
 
 use args::Args;
-use clap::Parser;
-use config::Config;
+use clap::{CommandFactory, Parser};
+use config::{Config, Mode};
+use std::io::IsTerminal;
+use std::process::ExitCode;
 use walker::Walker;
 
-fn main() {
+fn main() -> ExitCode {
     let args = Args::parse();
     let Args {
-        files,
+        mut files,
+        no_editorconfig,
+        ignore_path,
+        use_gitignore,
+        extensions,
+        stdin,
+        stdin_filepath,
         indent_style,
+        indent_size,
         max_line_length,
+        end_of_line,
+        single_attribute_per_line,
+        max_blank_lines,
+        no_insert_final_newline,
+        no_trim_trailing_whitespace,
+        jobs,
+        check,
+        diff,
+        verify,
+        safe,
+        minify,
+        minify_disable,
+        range_start,
+        range_end,
+        range_lines,
+        diff_context,
+        color,
+        print_config,
+        print_config_format,
     } = args;
 
     env_logger::init();
 
-    log::trace!("max_line_length = {}", max_line_length);
+    let stdin = stdin || files.iter().any(|file| file == "-");
+    if stdin && files.len() > 1 {
+        Args::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "- can't be combined with other files",
+            )
+            .exit();
+    }
+    files.retain(|file| file != "-");
+
+    let has_range = range_start.is_some() || range_end.is_some();
+    if has_range && !stdin && files.len() != 1 {
+        Args::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--range-start and --range-end require a single file or stdin",
+            )
+            .exit();
+    }
+
+    log::trace!("max_line_length = {:?}", max_line_length);
     log::trace!("files = {:#?}", files);
 
     let config = Config::new(files)
+        .set_editorconfig(!no_editorconfig)
+        .set_extensions(extensions)
+        .set_ignore(ignore_path, use_gitignore)
+        .set_stdin(stdin, stdin_filepath)
         .set_indent_style(indent_style)
-        .set_max_line_length(max_line_length);
+        .set_indent_size(indent_size.map(usize::from))
+        .set_max_line_length(max_line_length)
+        .set_end_of_line(end_of_line)
+        .set_single_attribute_per_line(single_attribute_per_line)
+        .set_max_blank_lines(max_blank_lines)
+        .set_insert_final_newline(!no_insert_final_newline)
+        .set_trim_trailing_whitespace(!no_trim_trailing_whitespace)
+        .set_mode(if check {
+            Mode::Check
+        } else if diff {
+            Mode::Diff
+        } else {
+            Mode::Write
+        })
+        .set_verify(verify)
+        .set_safe(safe)
+        .set_minify(minify, minify_disable)
+        .set_range(range_start, range_end, range_lines)
+        .set_jobs(jobs)
+        .set_diff_context(diff_context)
+        .set_color(match color.as_str() {
+            "always" => true,
+            "never" => false,
+            _ => std::io::stdout().is_terminal(),
+        })
+        .set_print_config(print_config, print_config_format);
     let walker = Walker::new(config);
 
-    walker.run();
+    walker.run()
 }
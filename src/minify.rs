@@ -0,0 +1,444 @@
+use crate::error::FormatError;
+use crate::models::{Comment, TagAttribute};
+use crate::tree::{self, Element, Node};
+use crate::utils::{is_preformatted_element, is_void_element, quote_attribute_value};
+use std::fmt::Write;
+
+const BOOLEAN_ATTRIBUTES: [&str; 24] = [
+    "allowfullscreen",
+    "async",
+    "autofocus",
+    "autoplay",
+    "checked",
+    "controls",
+    "default",
+    "defer",
+    "disabled",
+    "formnovalidate",
+    "hidden",
+    "inert",
+    "ismap",
+    "itemscope",
+    "loop",
+    "multiple",
+    "muted",
+    "nomodule",
+    "novalidate",
+    "open",
+    "playsinline",
+    "readonly",
+    "required",
+    "selected",
+];
+
+/// Elements after which `</p>` may be omitted
+const CLOSES_PARAGRAPH: [&str; 23] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "div",
+    "dl",
+    "fieldset",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "ul",
+];
+
+/// Minification steps, all of them are enabled by default
+#[derive(Debug, Clone)]
+pub struct MinifyOptions {
+    /// Collapse whitespace in text and drop it at the start and the end of children
+    pub collapse_whitespace: bool,
+    /// Remove comments except conditional and `<!--! -->` license comments
+    pub remove_comments: bool,
+    /// Omit end tags of `li`, `p`, table cells and similar where the next sibling closes them
+    pub remove_optional_end_tags: bool,
+    pub remove_attribute_quotes: bool,
+    /// Write `disabled` instead of `disabled="disabled"`
+    pub collapse_boolean_attributes: bool,
+    /// Remove `type="text/javascript"` of `script` and `type="text/css"` of `style`
+    pub remove_default_type: bool,
+}
+
+impl MinifyOptions {
+    pub fn new() -> Self {
+        MinifyOptions {
+            collapse_whitespace: true,
+            remove_comments: true,
+            remove_optional_end_tags: true,
+            remove_attribute_quotes: true,
+            collapse_boolean_attributes: true,
+            remove_default_type: true,
+        }
+    }
+}
+
+impl Default for MinifyOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MinifyOptions {
+    pub fn set_collapse_whitespace(mut self, collapse_whitespace: bool) -> Self {
+        self.collapse_whitespace = collapse_whitespace;
+
+        self
+    }
+
+    pub fn set_remove_comments(mut self, remove_comments: bool) -> Self {
+        self.remove_comments = remove_comments;
+
+        self
+    }
+
+    pub fn set_remove_optional_end_tags(mut self, remove_optional_end_tags: bool) -> Self {
+        self.remove_optional_end_tags = remove_optional_end_tags;
+
+        self
+    }
+
+    pub fn set_remove_attribute_quotes(mut self, remove_attribute_quotes: bool) -> Self {
+        self.remove_attribute_quotes = remove_attribute_quotes;
+
+        self
+    }
+
+    pub fn set_collapse_boolean_attributes(mut self, collapse_boolean_attributes: bool) -> Self {
+        self.collapse_boolean_attributes = collapse_boolean_attributes;
+
+        self
+    }
+
+    pub fn set_remove_default_type(mut self, remove_default_type: bool) -> Self {
+        self.remove_default_type = remove_default_type;
+
+        self
+    }
+}
+
+/// Minifies `content` with `options`
+pub fn minify_str(content: &str, options: &MinifyOptions) -> Result<String, FormatError> {
+    let nodes = tree::parse(content)?;
+    let mut minifier = Minifier {
+        options,
+        result: String::new(),
+    };
+
+    minifier.write_nodes(&nodes, None, false)?;
+
+    Ok(minifier.result)
+}
+
+struct Minifier<'a> {
+    options: &'a MinifyOptions,
+    result: String,
+}
+
+impl Minifier<'_> {
+    /// `preserve_whitespace` is set inside whitespace sensitive elements, including
+    /// their descendants
+    fn write_nodes(
+        &mut self,
+        nodes: &[Node<'_>],
+        parent: Option<&Element<'_>>,
+        preserve_whitespace: bool,
+    ) -> Result<(), FormatError> {
+        let collapse_whitespace = self.options.collapse_whitespace && !preserve_whitespace;
+
+        let nodes: Vec<&Node<'_>> = nodes
+            .iter()
+            .filter(|node| match node {
+                Node::Comment(comment) => !self.options.remove_comments || is_kept_comment(comment),
+                _ => true,
+            })
+            .collect();
+
+        // Whitespace after an omitted end tag would become part of the element,
+        // whitespace after the doctype isn't part of the document
+        let mut drop_whitespace = false;
+        // Whitespace at the edges of inline elements renders
+        let is_block_parent = parent.is_none_or(|parent| tree::is_block_element(&parent.tag_name));
+
+        for (index, node) in nodes.iter().enumerate() {
+            match node {
+                Node::Text(text) if collapse_whitespace => {
+                    let is_edge = index == 0 || index == nodes.len() - 1;
+
+                    self.write_collapsed_text(
+                        &text.data,
+                        drop_whitespace || (is_edge && is_block_parent),
+                    );
+
+                    if is_whitespace(node) {
+                        continue;
+                    }
+                }
+                Node::Text(text) => self.result.push_str(&text.data),
+                Node::Comment(comment) => write!(self.result, "<!--{}-->", comment.data)?,
+                Node::Doctype(doctype) => {
+                    self.result.push_str(&doctype.data);
+                    drop_whitespace = true;
+
+                    continue;
+                }
+                Node::Element(element) => {
+                    let next = nodes[index + 1..]
+                        .iter()
+                        .find(|node| !is_whitespace(node) || !collapse_whitespace);
+
+                    drop_whitespace =
+                        self.write_element(element, next.copied(), parent, preserve_whitespace)?;
+
+                    continue;
+                }
+            }
+
+            drop_whitespace = false;
+        }
+
+        Ok(())
+    }
+
+    /// Whitespace runs become a single space, whitespace only text is dropped with
+    /// `drop_whitespace`. Texts left adjacent by removed comments share a space.
+    fn write_collapsed_text(&mut self, data: &str, drop_whitespace: bool) {
+        let words: Vec<&str> = data.split_whitespace().collect();
+
+        if words.is_empty() {
+            if !drop_whitespace && !data.is_empty() && !self.result.ends_with(' ') {
+                self.result.push(' ');
+            }
+
+            return;
+        }
+
+        if data.starts_with(char::is_whitespace) && !self.result.ends_with(' ') {
+            self.result.push(' ');
+        }
+        self.result.push_str(&words.join(" "));
+        if data.ends_with(char::is_whitespace) {
+            self.result.push(' ');
+        }
+    }
+
+    /// Returns whether the end tag is omitted
+    fn write_element(
+        &mut self,
+        element: &Element<'_>,
+        next: Option<&Node<'_>>,
+        parent: Option<&Element<'_>>,
+        preserve_whitespace: bool,
+    ) -> Result<bool, FormatError> {
+        write!(self.result, "<{}", element.tag_name)?;
+
+        let is_void = is_void_element(&element.tag_name);
+        let attributes: Vec<_> = element
+            .attributes
+            .iter()
+            .filter(|attribute| {
+                !self.options.remove_default_type || !is_default_type(element, attribute)
+            })
+            .collect();
+
+        for (index, attribute) in attributes.iter().enumerate() {
+            // `/` after an unquoted value would become part of it
+            let keep_quotes = element.self_closing && !is_void && index == attributes.len() - 1;
+
+            self.write_attribute(attribute, keep_quotes)?;
+        }
+
+        if element.self_closing && !is_void {
+            // E.g. `<path/>` in SVG, `/` of void elements is dropped
+            self.result.push_str("/>");
+
+            return Ok(false);
+        }
+
+        self.result.push('>');
+
+        if is_void {
+            return Ok(false);
+        }
+
+        let preserve_whitespace = preserve_whitespace
+            || is_preformatted_element(&element.tag_name)
+            || element.tag_name.eq_ignore_ascii_case("style");
+
+        self.write_nodes(&element.children, Some(element), preserve_whitespace)?;
+
+        let omit_end_tag = self.options.remove_optional_end_tags
+            && is_end_tag_optional(&element.tag_name, next, parent);
+
+        if element.end_loc.is_some() && !omit_end_tag {
+            write!(self.result, "</{}>", element.tag_name)?;
+        }
+
+        Ok(omit_end_tag)
+    }
+
+    fn write_attribute(
+        &mut self,
+        attribute: &TagAttribute<'_>,
+        keep_quotes: bool,
+    ) -> Result<(), FormatError> {
+        let name = &attribute.attribute_name;
+        let value = &attribute.attribute_value;
+        let is_boolean = BOOLEAN_ATTRIBUTES
+            .iter()
+            .any(|boolean| name.eq_ignore_ascii_case(boolean));
+
+        if attribute.value_loc.is_none() || (self.options.collapse_boolean_attributes && is_boolean)
+        {
+            write!(self.result, " {}", name)?;
+        } else if self.options.remove_attribute_quotes && !keep_quotes && can_be_unquoted(value) {
+            write!(self.result, " {}={}", name, value)?;
+        } else {
+            write!(self.result, " {}={}", name, quote_attribute_value(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_whitespace(node: &Node<'_>) -> bool {
+    matches!(node, Node::Text(text) if text.data.trim().is_empty())
+}
+
+/// Conditional comments and `<!--! -->` license comments
+fn is_kept_comment(comment: &Comment<'_>) -> bool {
+    let data = comment.data.trim();
+
+    data.starts_with("[if") || data.ends_with("<![endif]") || comment.data.starts_with('!')
+}
+
+fn is_default_type(element: &Element<'_>, attribute: &TagAttribute<'_>) -> bool {
+    let default_type = match element.tag_name.to_ascii_lowercase().as_str() {
+        "script" => "text/javascript",
+        "style" => "text/css",
+        _ => return false,
+    };
+
+    attribute.attribute_name.eq_ignore_ascii_case("type")
+        && attribute
+            .attribute_value
+            .trim()
+            .eq_ignore_ascii_case(default_type)
+}
+
+fn can_be_unquoted(value: &str) -> bool {
+    !value.is_empty()
+        && !value
+            .chars()
+            .any(|char| char.is_whitespace() || "\"'=<>`".contains(char))
+}
+
+/// End tag may be omitted if the next sibling element implies it or the element is the
+/// last child, a subset of the HTML spec rules which doesn't depend on text after it
+fn is_end_tag_optional(
+    tag_name: &str,
+    next: Option<&Node<'_>>,
+    parent: Option<&Element<'_>>,
+) -> bool {
+    let next_tag_name = match next {
+        None => None,
+        Some(Node::Element(element)) => Some(element.tag_name.to_ascii_lowercase()),
+        Some(_) => return false,
+    };
+    let next_is = |names: &[&str]| {
+        next_tag_name
+            .as_deref()
+            .is_none_or(|next| names.contains(&next))
+    };
+
+    match tag_name.to_ascii_lowercase().as_str() {
+        "li" => next_is(&["li"]),
+        "dt" | "dd" => next_is(&["dt", "dd"]),
+        "option" => next_is(&["option", "optgroup"]),
+        "tr" => next_is(&["tr"]),
+        "td" | "th" => next_is(&["td", "th"]),
+        "p" => {
+            let parent_allows = parent.is_none_or(|parent| {
+                !["a", "audio", "del", "ins", "map", "noscript", "video"]
+                    .iter()
+                    .any(|name| parent.tag_name.eq_ignore_ascii_case(name))
+            });
+
+            match next_tag_name {
+                Some(next) => CLOSES_PARAGRAPH.contains(&next.as_str()),
+                None => parent.is_some() && parent_allows,
+            }
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{minify_str, MinifyOptions};
+
+    #[test]
+    fn minifies() {
+        let content = "<!DOCTYPE html>\n<!-- note -->\n<div class=\"a b\" id=\"main\">\n  <ul>\n    <li>One   two</li>\n    <li><b>bold</b> <i>text</i></li>\n  </ul>\n  <!--! license -->\n  <input type=\"checkbox\" checked=\"checked\" value=''>\n  <script type=\"text/javascript\">\n  let a  = 1;\n</script>\n</div>\n";
+
+        assert_eq!(
+            minify_str(content, &MinifyOptions::new()).unwrap(),
+            "<!DOCTYPE html><div class=\"a b\" id=main><ul><li>One two<li><b>bold</b> <i>text</i></ul> <!--! license --> <input type=checkbox checked value=\"\"> <script>\n  let a  = 1;\n</script></div>"
+        );
+    }
+
+    #[test]
+    fn keeps_whitespace_in_descendants_of_pre() {
+        let content = "<pre><b>  x\n   y  </b></pre>";
+
+        assert_eq!(minify_str(content, &MinifyOptions::new()).unwrap(), content);
+    }
+
+    #[test]
+    fn keeps_whitespace_at_edges_of_inline_elements() {
+        let content = "<p>a<span> </span>b<b>\n c </b></p>\n<div> <i>d</i> </div>";
+
+        assert_eq!(
+            minify_str(content, &MinifyOptions::new()).unwrap(),
+            "<p>a<span> </span>b<b> c </b><div><i>d</i></div>"
+        );
+    }
+
+    #[test]
+    fn keeps_quotes_before_self_closing_slash() {
+        let content = "<svg><circle r=\"5\" cx=\"1\"/><path d=\"M0\" fill/></svg>";
+
+        assert_eq!(
+            minify_str(content, &MinifyOptions::new()).unwrap(),
+            "<svg><circle r=5 cx=\"1\"/><path d=M0 fill/></svg>"
+        );
+    }
+
+    #[test]
+    fn optimizations_can_be_disabled() {
+        let content = "<ul>\n<li title=\"x\">a</li>\n</ul><!-- c -->";
+        let options = MinifyOptions::new()
+            .set_remove_optional_end_tags(false)
+            .set_remove_attribute_quotes(false)
+            .set_remove_comments(false);
+
+        assert_eq!(
+            minify_str(content, &options).unwrap(),
+            "<ul><li title=\"x\">a</li></ul><!-- c -->"
+        );
+    }
+}
@@ -1,9 +1,13 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::str::FromStr;
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct LocationPos {
     pub line: usize,
     pub column: usize,
+    /// Byte offset in the source, so `&source[start.offset..end.offset]` is the token
+    pub offset: usize,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -12,103 +16,116 @@ pub struct Location {
     pub end: LocationPos,
 }
 
-pub trait Token {
+pub(crate) trait Token {
     fn add_loc(&mut self, loc: Location);
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct TagAttribute {
-    pub attribute_name: String,
-    pub attribute_value: String,
-}
-
-impl TagAttribute {
-    pub fn append_to_attribute_name(&mut self, char: char) {
-        let mut buffer = [0; 4];
-        self.attribute_name.push_str(char.encode_utf8(&mut buffer));
-    }
-
-    pub fn append_to_attribute_value(&mut self, char: char) {
-        let mut buffer = [0; 4];
-        self.attribute_value.push_str(char.encode_utf8(&mut buffer));
-    }
+pub struct TagAttribute<'a> {
+    pub attribute_name: Cow<'a, str>,
+    pub attribute_value: Cow<'a, str>,
+    pub name_loc: Option<Location>,
+    /// Span of the value without surrounding quotes, `None` for attributes without value
+    pub value_loc: Option<Location>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct StartTag {
-    pub tag_name: String,
-    pub attributes: Vec<TagAttribute>,
+pub struct StartTag<'a> {
+    pub tag_name: Cow<'a, str>,
+    pub attributes: Vec<TagAttribute<'a>>,
     pub self_closing: bool,
     pub loc: Option<Location>,
 }
 
-impl StartTag {
-    pub fn append_to_tag_name(&mut self, char: char) {
-        let mut buffer = [0; 4];
-        self.tag_name.push_str(char.encode_utf8(&mut buffer));
-    }
-
-    pub fn append_to_attributes(&mut self, tag_attribute: TagAttribute) {
+impl<'a> StartTag<'a> {
+    pub(crate) fn append_to_attributes(&mut self, tag_attribute: TagAttribute<'a>) {
         self.attributes.push(tag_attribute);
     }
 
-    pub fn mark_as_self_closing(&mut self) {
+    pub(crate) fn mark_as_self_closing(&mut self) {
         self.self_closing = true;
     }
 }
 
-impl Token for StartTag {
+impl Token for StartTag<'_> {
     fn add_loc(&mut self, loc: Location) {
         self.loc = Some(loc);
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct EndTag {
-    pub tag_name: String,
+pub struct EndTag<'a> {
+    pub tag_name: Cow<'a, str>,
     pub loc: Option<Location>,
 }
 
-impl EndTag {
-    pub fn append_to_tag_name(&mut self, char: char) {
-        let mut buffer = [0; 4];
-        self.tag_name.push_str(char.encode_utf8(&mut buffer));
+impl Token for EndTag<'_> {
+    fn add_loc(&mut self, loc: Location) {
+        self.loc = Some(loc);
     }
 }
 
-impl Token for EndTag {
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Chars<'a> {
+    pub data: Cow<'a, str>,
+    pub loc: Option<Location>,
+}
+
+impl Token for Chars<'_> {
     fn add_loc(&mut self, loc: Location) {
         self.loc = Some(loc);
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct Chars {
-    pub data: String,
+pub struct Comment<'a> {
+    /// Content between `<!--` and `-->`
+    pub data: Cow<'a, str>,
     pub loc: Option<Location>,
 }
 
-impl Chars {
-    pub fn append_to_chars(&mut self, char: char) {
-        let mut buffer = [0; 4];
-        self.data.push_str(char.encode_utf8(&mut buffer));
+impl Token for Comment<'_> {
+    fn add_loc(&mut self, loc: Location) {
+        self.loc = Some(loc);
     }
 }
 
-impl Token for Chars {
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Doctype<'a> {
+    /// Whole declaration, e.g. `<!DOCTYPE html>`, it's written as is
+    pub data: Cow<'a, str>,
+    pub loc: Option<Location>,
+}
+
+impl Token for Doctype<'_> {
     fn add_loc(&mut self, loc: Location) {
         self.loc = Some(loc);
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub enum LexerToken {
-    StartTag(StartTag),
-    EndTag(EndTag),
-    Chars(Chars),
+pub enum LexerToken<'a> {
+    StartTag(StartTag<'a>),
+    EndTag(EndTag<'a>),
+    Chars(Chars<'a>),
+    Comment(Comment<'a>),
+    Doctype(Doctype<'a>),
+}
+
+impl LexerToken<'_> {
+    pub fn loc(&self) -> Option<&Location> {
+        match self {
+            Self::StartTag(tag) => tag.loc.as_ref(),
+            Self::EndTag(tag) => tag.loc.as_ref(),
+            Self::Chars(chars) => chars.loc.as_ref(),
+            Self::Comment(comment) => comment.loc.as_ref(),
+            Self::Doctype(doctype) => doctype.loc.as_ref(),
+        }
+    }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum IndentStyle {
     #[default]
     Tab,
@@ -135,3 +152,39 @@ impl FromStr for IndentStyle {
         }
     }
 }
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EndOfLine {
+    #[default]
+    Lf,
+    Crlf,
+    /// Use the first line ending found in the input
+    Auto,
+}
+
+impl EndOfLine {
+    pub fn resolve(&self, input: &str) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+            Self::Auto => match input.find('\n') {
+                Some(index) if input[..index].ends_with('\r') => "\r\n",
+                _ => "\n",
+            },
+        }
+    }
+}
+
+impl FromStr for EndOfLine {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lf" => Ok(Self::Lf),
+            "crlf" => Ok(Self::Crlf),
+            "auto" => Ok(Self::Auto),
+            _ => Err("Not valid end of line"),
+        }
+    }
+}
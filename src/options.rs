@@ -0,0 +1,185 @@
+use crate::models::{EndOfLine, IndentStyle};
+use serde::{Deserialize, Serialize};
+
+/// Formatting options resolved for a single file
+#[derive(Debug, Clone, Serialize)]
+pub struct Options {
+    pub indent_style: IndentStyle,
+    /// Number of spaces per indent level, ignored for tabs
+    pub indent_size: usize,
+    pub max_line_length: i32,
+    pub end_of_line: EndOfLine,
+    /// Break tag attributes on separate lines even if they fit into `max_line_length`
+    pub single_attribute_per_line: bool,
+    /// Number of consecutive blank lines kept between nodes
+    pub max_blank_lines: usize,
+    /// End output with a line break
+    pub insert_final_newline: bool,
+    /// Remove whitespace at the end of text lines, attribute values and comments are kept
+    pub trim_trailing_whitespace: bool,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Options {
+            indent_style: IndentStyle::Tab,
+            indent_size: 2,
+            max_line_length: 80,
+            end_of_line: EndOfLine::Lf,
+            single_attribute_per_line: false,
+            max_blank_lines: 1,
+            insert_final_newline: true,
+            trim_trailing_whitespace: true,
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Options {
+    pub fn set_indent_style(mut self, indent_style: IndentStyle) -> Self {
+        self.indent_style = indent_style;
+
+        self
+    }
+
+    pub fn set_indent_size(mut self, indent_size: usize) -> Self {
+        self.indent_size = indent_size;
+
+        self
+    }
+
+    pub fn set_max_line_length(mut self, max_line_length: i32) -> Self {
+        self.max_line_length = max_line_length;
+
+        self
+    }
+
+    pub fn set_end_of_line(mut self, end_of_line: EndOfLine) -> Self {
+        self.end_of_line = end_of_line;
+
+        self
+    }
+
+    pub fn set_single_attribute_per_line(mut self, single_attribute_per_line: bool) -> Self {
+        self.single_attribute_per_line = single_attribute_per_line;
+
+        self
+    }
+
+    pub fn set_max_blank_lines(mut self, max_blank_lines: usize) -> Self {
+        self.max_blank_lines = max_blank_lines;
+
+        self
+    }
+
+    pub fn set_insert_final_newline(mut self, insert_final_newline: bool) -> Self {
+        self.insert_final_newline = insert_final_newline;
+
+        self
+    }
+
+    pub fn set_trim_trailing_whitespace(mut self, trim_trailing_whitespace: bool) -> Self {
+        self.trim_trailing_whitespace = trim_trailing_whitespace;
+
+        self
+    }
+
+    /// Overrides options which are set in `partial`
+    pub fn merge(mut self, partial: &PartialOptions) -> Self {
+        if let Some(indent_style) = partial.indent_style {
+            self = self.set_indent_style(indent_style);
+        }
+        if let Some(indent_size) = partial.indent_size {
+            self = self.set_indent_size(indent_size);
+        }
+        if let Some(max_line_length) = partial.max_line_length {
+            self = self.set_max_line_length(max_line_length);
+        }
+        if let Some(end_of_line) = partial.end_of_line {
+            self = self.set_end_of_line(end_of_line);
+        }
+        if let Some(single_attribute_per_line) = partial.single_attribute_per_line {
+            self = self.set_single_attribute_per_line(single_attribute_per_line);
+        }
+        if let Some(max_blank_lines) = partial.max_blank_lines {
+            self = self.set_max_blank_lines(max_blank_lines);
+        }
+        if let Some(insert_final_newline) = partial.insert_final_newline {
+            self = self.set_insert_final_newline(insert_final_newline);
+        }
+        if let Some(trim_trailing_whitespace) = partial.trim_trailing_whitespace {
+            self = self.set_trim_trailing_whitespace(trim_trailing_whitespace);
+        }
+
+        self
+    }
+
+    pub fn indent(&self, level: i32) -> String {
+        match self.indent_style {
+            IndentStyle::Tab => self.indent_style.repeat(level),
+            IndentStyle::Space => self.indent_style.repeat(level * self.indent_size as i32),
+        }
+    }
+}
+
+/// Options set by a single source, e.g. config file or CLI flags
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialOptions {
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<usize>,
+    pub max_line_length: Option<i32>,
+    pub end_of_line: Option<EndOfLine>,
+    pub single_attribute_per_line: Option<bool>,
+    pub max_blank_lines: Option<usize>,
+    pub insert_final_newline: Option<bool>,
+    pub trim_trailing_whitespace: Option<bool>,
+}
+
+impl PartialOptions {
+    /// Names of the options which are set
+    pub fn keys(&self) -> Vec<&'static str> {
+        [
+            ("indent_style", self.indent_style.is_some()),
+            ("indent_size", self.indent_size.is_some()),
+            ("max_line_length", self.max_line_length.is_some()),
+            ("end_of_line", self.end_of_line.is_some()),
+            (
+                "single_attribute_per_line",
+                self.single_attribute_per_line.is_some(),
+            ),
+            ("max_blank_lines", self.max_blank_lines.is_some()),
+            ("insert_final_newline", self.insert_final_newline.is_some()),
+            (
+                "trim_trailing_whitespace",
+                self.trim_trailing_whitespace.is_some(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, is_set)| is_set.then_some(key))
+        .collect()
+    }
+
+    /// Returns the invalid key with the reason
+    pub fn validate(&self) -> Result<(), (&'static str, String)> {
+        if let Some(max_line_length) = self.max_line_length {
+            if max_line_length <= 0 {
+                return Err((
+                    "max_line_length",
+                    format!("must be positive, got {}", max_line_length),
+                ));
+            }
+        }
+
+        if self.indent_size == Some(0) {
+            return Err(("indent_size", "must be positive, got 0".to_owned()));
+        }
+
+        Ok(())
+    }
+}
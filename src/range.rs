@@ -0,0 +1,180 @@
+use crate::error::FormatError;
+use crate::format_str;
+use crate::models::EndOfLine;
+use crate::options::Options;
+use crate::tree::{self, Node};
+use std::ops::Range;
+
+/// Formats the smallest list of sibling nodes covering `range` (byte offsets) and
+/// returns the whole content, everything outside of these nodes is kept as is.
+/// Formatted nodes are indented by the number of elements enclosing them, the first
+/// line too if only whitespace precedes the nodes on it.
+pub fn format_range(
+    content: &str,
+    range: Range<usize>,
+    options: &Options,
+) -> Result<String, FormatError> {
+    let nodes = tree::parse(content)?;
+
+    let Some((region, depth)) = find_region(&nodes, &range, 0) else {
+        return Ok(content.to_owned());
+    };
+
+    // Formatter would resolve `auto` from the region, which may have no line breaks
+    let line_ending = options.end_of_line.resolve(content);
+    let options = options.clone().set_end_of_line(if line_ending == "\r\n" {
+        EndOfLine::Crlf
+    } else {
+        EndOfLine::Lf
+    });
+
+    // Whitespace before the region on its first line is replaced by the indent
+    let line_start = content[..region.start]
+        .rfind(['\r', '\n'])
+        .map_or(0, |index| index + 1);
+    let (start, first_indent) = if content[line_start..region.start].trim().is_empty() {
+        (line_start, options.indent(depth))
+    } else {
+        (region.start, String::new())
+    };
+
+    let formatted = format_str(&content[region.clone()], &options)?;
+    let indented = formatted
+        .trim_end_matches(['\r', '\n'])
+        .split(line_ending)
+        .collect::<Vec<_>>()
+        .join(&format!("{}{}", line_ending, options.indent(depth)));
+
+    Ok(format!(
+        "{}{}{}{}",
+        &content[..start],
+        first_indent,
+        indented,
+        &content[region.end..]
+    ))
+}
+
+/// Byte range of the nodes to format with their depth, `None` if `range`
+/// covers only whitespace
+fn find_region(
+    nodes: &[Node<'_>],
+    range: &Range<usize>,
+    depth: i32,
+) -> Option<(Range<usize>, i32)> {
+    let overlapping: Vec<(&Node<'_>, Range<usize>)> = nodes
+        .iter()
+        .filter(|node| match node {
+            Node::Text(text) => !text.significant().is_empty(),
+            Node::Element(_) | Node::Comment(_) | Node::Doctype(_) => true,
+        })
+        .filter_map(|node| {
+            let span = span(node)?;
+            // Empty range is a cursor position inside the node
+            let overlaps = span.start < range.end.max(range.start + 1) && range.start < span.end;
+
+            overlaps.then_some((node, span))
+        })
+        .collect();
+
+    let (first, last) = (overlapping.first()?, overlapping.last()?);
+
+    if let [(Node::Element(element), _)] = overlapping.as_slice() {
+        if let (Some(loc), Some(end_loc)) = (&element.loc, &element.end_loc) {
+            let is_inside = range.start >= loc.end.offset && range.end <= end_loc.start.offset;
+
+            if is_inside {
+                return find_region(&element.children, range, depth + 1);
+            }
+        }
+    }
+
+    Some((first.1.start..last.1.end, depth))
+}
+
+/// Byte range of the node including its end tag and children
+fn span(node: &Node<'_>) -> Option<Range<usize>> {
+    match node {
+        Node::Text(_) | Node::Comment(_) | Node::Doctype(_) => {
+            node.loc().map(|loc| loc.start.offset..loc.end.offset)
+        }
+        Node::Element(element) => {
+            let loc = element.loc.as_ref()?;
+            let end = match &element.end_loc {
+                Some(end_loc) => end_loc.end.offset,
+                None => element
+                    .children
+                    .iter()
+                    .filter_map(span)
+                    .map(|span| span.end)
+                    .fold(loc.end.offset, usize::max),
+            };
+
+            Some(loc.start.offset..end)
+        }
+    }
+}
+
+/// Byte range from the start of `start_line` to the end of `end_line` without
+/// its line break, lines are counted from 1
+pub fn line_range(content: &str, start_line: usize, end_line: usize) -> Range<usize> {
+    let mut line_starts = vec![0];
+    line_starts.extend(content.match_indices('\n').map(|(index, _)| index + 1));
+
+    let line_start = |line: usize| line_starts.get(line.max(1) - 1).copied();
+    let start = line_start(start_line).unwrap_or(content.len());
+    let end = line_start(end_line + 1)
+        .map(|next_line_start| {
+            content[..next_line_start]
+                .trim_end_matches(['\r', '\n'])
+                .len()
+        })
+        .unwrap_or(content.len())
+        .max(start);
+
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_range, line_range};
+    use crate::options::Options;
+
+    #[test]
+    fn formats_only_nodes_in_range() {
+        let content = "<div>\n  <p>a</p>\n\n      <span>b</span>   <i>c</i>\n</div>\n<ul>  </ul>";
+        let offset = content.find("<span>").unwrap() + 2;
+
+        let formatted = format_range(content, offset..offset, &Options::new()).unwrap();
+
+        assert_eq!(
+            formatted,
+            "<div>\n  <p>a</p>\n\n\t<span>\n\t\tb\n\t</span>   <i>c</i>\n</div>\n<ul>  </ul>"
+        );
+
+        let content = "<div>\n<p>a</p>\n</div>";
+        let formatted = format_range(content, line_range(content, 2, 2), &Options::new()).unwrap();
+
+        assert_eq!(formatted, "<div>\n\t<p>\n\t\ta\n\t</p>\n</div>");
+    }
+
+    #[test]
+    fn selects_siblings_covering_range() {
+        let content = "<div><p>a</p><p>b</p><p>c</p></div>";
+        let range = content.find("a</p>").unwrap()..content.find("b</p>").unwrap();
+
+        let formatted = format_range(content, range, &Options::new()).unwrap();
+
+        assert_eq!(
+            formatted,
+            "<div><p>\n\t\ta\n\t</p>\n\t<p>\n\t\tb\n\t</p><p>c</p></div>"
+        );
+    }
+
+    #[test]
+    fn line_range_excludes_last_line_break() {
+        let content = "a\r\nbc\r\nd";
+
+        assert_eq!(&content[line_range(content, 2, 2)], "bc");
+        assert_eq!(&content[line_range(content, 1, 9)], content);
+    }
+}
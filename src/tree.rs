@@ -0,0 +1,665 @@
+use crate::error::{FormatError, ParseError};
+use crate::input_stream::InputStream;
+use crate::lexer::Lexer;
+use crate::models::{Comment, Doctype, LexerToken, Location, TagAttribute};
+use crate::utils::is_void_element;
+use std::borrow::Cow;
+use std::fmt;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Node<'a> {
+    Element(Element<'a>),
+    Text(Text<'a>),
+    Comment(Comment<'a>),
+    Doctype(Doctype<'a>),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Element<'a> {
+    pub tag_name: Cow<'a, str>,
+    pub attributes: Vec<TagAttribute<'a>>,
+    pub children: Vec<Node<'a>>,
+    pub self_closing: bool,
+    /// Location of the start tag
+    pub loc: Option<Location>,
+    /// Location of the end tag, `None` for void and implicitly closed elements
+    pub end_loc: Option<Location>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Text<'a> {
+    pub data: Cow<'a, str>,
+    pub loc: Option<Location>,
+}
+
+impl Text<'_> {
+    /// Text with whitespace runs collapsed into a single space and trimmed,
+    /// as the formatter is free to re-indent and re-wrap it
+    pub fn significant(&self) -> String {
+        self.data.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+impl Node<'_> {
+    /// Location of the start tag or the text
+    pub fn loc(&self) -> Option<&Location> {
+        match self {
+            Self::Element(element) => element.loc.as_ref(),
+            Self::Text(text) => text.loc.as_ref(),
+            Self::Comment(comment) => comment.loc.as_ref(),
+            Self::Doctype(doctype) => doctype.loc.as_ref(),
+        }
+    }
+}
+
+/// Builds the document tree. Void and self-closing elements have no children,
+/// an end tag closes the nearest open element with its name in any case, stray end
+/// tags are skipped.
+pub fn parse(content: &str) -> Result<Vec<Node<'_>>, ParseError> {
+    let mut lexer = Lexer::new(InputStream::new(content));
+    // Open elements, the root list of nodes is kept in an element without name
+    let mut stack = vec![Element {
+        tag_name: Cow::Borrowed(""),
+        attributes: vec![],
+        children: vec![],
+        self_closing: false,
+        loc: None,
+        end_loc: None,
+    }];
+
+    for token in &mut lexer {
+        match token {
+            LexerToken::StartTag(start_tag) => {
+                let element = Element {
+                    tag_name: start_tag.tag_name,
+                    attributes: start_tag.attributes,
+                    children: vec![],
+                    self_closing: start_tag.self_closing,
+                    loc: start_tag.loc,
+                    end_loc: None,
+                };
+
+                if element.self_closing || is_void_element(&element.tag_name) {
+                    push_node(&mut stack, Node::Element(element));
+                } else {
+                    stack.push(element);
+                }
+            }
+            LexerToken::EndTag(end_tag) => {
+                let open_index = stack
+                    .iter()
+                    .skip(1)
+                    .rposition(|element| element.tag_name.eq_ignore_ascii_case(&end_tag.tag_name));
+
+                if let Some(open_index) = open_index {
+                    stack[open_index + 1].end_loc = end_tag.loc;
+
+                    // Elements left open inside are closed by this end tag
+                    while stack.len() > open_index + 1 {
+                        let element = stack.pop().unwrap();
+                        push_node(&mut stack, Node::Element(element));
+                    }
+                }
+            }
+            LexerToken::Chars(chars) => push_node(
+                &mut stack,
+                Node::Text(Text {
+                    data: chars.data,
+                    loc: chars.loc,
+                }),
+            ),
+            LexerToken::Comment(comment) => push_node(&mut stack, Node::Comment(comment)),
+            LexerToken::Doctype(doctype) => push_node(&mut stack, Node::Doctype(doctype)),
+        }
+    }
+
+    if let Some(error) = lexer.error() {
+        return Err(error.clone());
+    }
+
+    while stack.len() > 1 {
+        let element = stack.pop().unwrap();
+        push_node(&mut stack, Node::Element(element));
+    }
+
+    Ok(stack.pop().unwrap().children)
+}
+
+fn push_node<'a>(stack: &mut [Element<'a>], node: Node<'a>) {
+    stack.last_mut().unwrap().children.push(node);
+}
+
+/// First difference found between the original and the formatted trees
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Divergence {
+    pub message: String,
+    pub original_loc: Option<Location>,
+    pub formatted_loc: Option<Location>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = |loc: &Option<Location>| match loc {
+            Some(loc) => format!("{}:{}", loc.start.line, loc.start.column),
+            None => "end of file".to_owned(),
+        };
+
+        write!(
+            f,
+            "{} (original {}, formatted {})",
+            self.message,
+            position(&self.original_loc),
+            position(&self.formatted_loc)
+        )
+    }
+}
+
+/// Checks that `formatted` renders the same tree as `original`: element names,
+/// attributes, doctypes, comments and text. Whitespace in text is collapsed, and
+/// ignored only at the start and the end of children and next to block elements.
+/// Text of `pre`, `textarea` and `script` is compared as is, `<style>` as CSS.
+pub fn check_equivalent(original: &str, formatted: &str) -> Result<(), FormatError> {
+    let original_nodes = parse(original)?;
+    let formatted_nodes = parse(formatted)?;
+
+    match compare_nodes(
+        &original_nodes,
+        &formatted_nodes,
+        None,
+        None,
+        TextMode::Collapse,
+    ) {
+        Some(divergence) => Err(FormatError::Unsafe(Box::new(divergence))),
+        None => Ok(()),
+    }
+}
+
+/// Elements laid out as blocks or not rendered, whitespace next to them doesn't
+/// render. Other elements, including unknown ones, are treated as inline.
+const BLOCK_ELEMENTS: [&str; 70] = [
+    "address",
+    "article",
+    "aside",
+    "base",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "col",
+    "colgroup",
+    "datalist",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "frame",
+    "frameset",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "legend",
+    "li",
+    "link",
+    "listing",
+    "main",
+    "menu",
+    "meta",
+    "nav",
+    "noframes",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "param",
+    "plaintext",
+    "pre",
+    "script",
+    "search",
+    "section",
+    "source",
+    "style",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "track",
+    "ul",
+    "xmp",
+];
+
+/// How text of the compared children is normalized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextMode {
+    Collapse,
+    /// Whitespace sensitive and raw text elements with their descendants
+    Exact,
+    Css,
+}
+
+impl TextMode {
+    fn of_children(self, element: &Element<'_>) -> Self {
+        let tag_name = element.tag_name.to_ascii_lowercase();
+
+        match tag_name.as_str() {
+            _ if self == Self::Exact => Self::Exact,
+            "pre" | "textarea" | "script" => Self::Exact,
+            "style" => Self::Css,
+            _ => Self::Collapse,
+        }
+    }
+}
+
+/// Node with its text normalized for comparison
+enum Item<'n, 'a> {
+    Node(&'n Node<'a>),
+    Text(String, Option<&'n Location>),
+}
+
+impl Item<'_, '_> {
+    fn loc(&self) -> Option<&Location> {
+        match self {
+            Self::Node(node) => node.loc(),
+            Self::Text(_, loc) => *loc,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Node(node) => describe(node),
+            Self::Text(text, _) => format!("text {:?}", text),
+        }
+    }
+}
+
+fn is_block(node: Option<&Node<'_>>) -> bool {
+    match node {
+        None | Some(Node::Doctype(_)) => true,
+        Some(Node::Element(element)) => is_block_element(&element.tag_name),
+        Some(Node::Text(_) | Node::Comment(_)) => false,
+    }
+}
+
+pub(crate) fn is_block_element(tag_name: &str) -> bool {
+    BLOCK_ELEMENTS
+        .iter()
+        .any(|name| tag_name.eq_ignore_ascii_case(name))
+}
+
+/// Text which is left after normalization, e.g. whitespace between block elements
+/// is dropped as the formatter is free to re-indent it
+fn significant_items<'n, 'a>(nodes: &'n [Node<'a>], mode: TextMode) -> Vec<Item<'n, 'a>> {
+    let mut items = vec![];
+
+    for (index, node) in nodes.iter().enumerate() {
+        let Node::Text(text) = node else {
+            items.push(Item::Node(node));
+            continue;
+        };
+
+        let data = match mode {
+            TextMode::Exact => text.data.to_string(),
+            TextMode::Css => css_significant(&text.data),
+            TextMode::Collapse => {
+                let mut data = collapse_whitespace(&text.data);
+
+                if is_block(nodes.get(index + 1)) {
+                    data.truncate(data.trim_end().len());
+                }
+                if index == 0 || is_block(nodes.get(index - 1)) {
+                    data = data.trim_start().to_owned();
+                }
+
+                data
+            }
+        };
+
+        if !data.is_empty() {
+            items.push(Item::Text(data, node.loc()));
+        }
+    }
+
+    items
+}
+
+fn collapse_whitespace(data: &str) -> String {
+    let mut result = String::with_capacity(data.len());
+
+    for char in data.chars() {
+        if !char.is_whitespace() {
+            result.push(char);
+        } else if !result.ends_with(' ') {
+            result.push(' ');
+        }
+    }
+
+    result
+}
+
+/// CSS with whitespace collapsed to a single space and removed around punctuation,
+/// which the CSS formatter re-spaces, strings are kept as is. Empty declarations and
+/// `;` before `}` are removed, as the CSS formatter drops and adds them.
+fn css_significant(data: &str) -> String {
+    const PUNCTUATION: &str = "{};:,";
+
+    let mut css = String::with_capacity(data.len());
+    let mut chars = data.chars();
+    let mut pending_space = false;
+
+    while let Some(char) = chars.next() {
+        if char.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+
+        let after_punctuation = css.ends_with(|last| PUNCTUATION.contains(last));
+        if pending_space && !css.is_empty() && !after_punctuation && !PUNCTUATION.contains(char) {
+            css.push(' ');
+        }
+        pending_space = false;
+        css.push(char);
+
+        if char == '"' || char == '\'' {
+            let mut escaped = false;
+
+            for string_char in chars.by_ref() {
+                css.push(string_char);
+
+                if escaped {
+                    escaped = false;
+                } else if string_char == '\\' {
+                    escaped = true;
+                } else if string_char == char {
+                    break;
+                }
+            }
+        }
+    }
+
+    while css.contains(";;") || css.contains(";}") || css.contains("{;") {
+        css = css.replace(";;", ";").replace(";}", "}").replace("{;", "{");
+    }
+
+    css.trim_end_matches(';').to_owned()
+}
+
+fn compare_nodes(
+    original: &[Node<'_>],
+    formatted: &[Node<'_>],
+    original_parent: Option<&Location>,
+    formatted_parent: Option<&Location>,
+    mode: TextMode,
+) -> Option<Divergence> {
+    let mut original = significant_items(original, mode).into_iter();
+    let mut formatted = significant_items(formatted, mode).into_iter();
+
+    loop {
+        let divergence = |message: String, a: Option<&Location>, b: Option<&Location>| {
+            Some(Divergence {
+                message,
+                original_loc: a.cloned(),
+                formatted_loc: b.cloned(),
+            })
+        };
+
+        let (a, b) = match (original.next(), formatted.next()) {
+            (None, None) => return None,
+            (Some(a), None) => {
+                return divergence(
+                    format!("{} is missing", a.describe()),
+                    a.loc(),
+                    formatted_parent,
+                )
+            }
+            (None, Some(b)) => {
+                return divergence(
+                    format!("{} is added", b.describe()),
+                    original_parent,
+                    b.loc(),
+                )
+            }
+            (Some(a), Some(b)) => (a, b),
+        };
+
+        let (node_a, node_b) = match (&a, &b) {
+            (Item::Text(text_a, _), Item::Text(text_b, _)) => {
+                if text_a != text_b {
+                    return divergence(
+                        format!("text {:?} is changed to {:?}", text_a, text_b),
+                        a.loc(),
+                        b.loc(),
+                    );
+                }
+
+                continue;
+            }
+            (Item::Node(node_a), Item::Node(node_b)) => (*node_a, *node_b),
+            _ => {
+                return divergence(
+                    format!("{} is changed to {}", a.describe(), b.describe()),
+                    a.loc(),
+                    b.loc(),
+                )
+            }
+        };
+
+        match (node_a, node_b) {
+            (Node::Comment(comment_a), Node::Comment(comment_b)) => {
+                if comment_a.data.trim() != comment_b.data.trim() {
+                    return divergence(
+                        format!("{} is changed to {}", a.describe(), b.describe()),
+                        a.loc(),
+                        b.loc(),
+                    );
+                }
+            }
+            (Node::Doctype(doctype_a), Node::Doctype(doctype_b)) => {
+                if doctype_a.data != doctype_b.data {
+                    return divergence(
+                        format!("{} is changed to {}", a.describe(), b.describe()),
+                        a.loc(),
+                        b.loc(),
+                    );
+                }
+            }
+            (Node::Element(element_a), Node::Element(element_b)) => {
+                if element_a.tag_name != element_b.tag_name {
+                    return divergence(
+                        format!(
+                            "element <{}> is changed to <{}>",
+                            element_a.tag_name, element_b.tag_name
+                        ),
+                        a.loc(),
+                        b.loc(),
+                    );
+                }
+
+                let attributes = |element: &Element<'_>| {
+                    element
+                        .attributes
+                        .iter()
+                        .map(|attribute| {
+                            // The formatter may escape `"` when it changes quotes
+                            format!(
+                                "{}={:?}",
+                                attribute.attribute_name,
+                                attribute.attribute_value.replace("&quot;", "\"")
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                };
+
+                if attributes(element_a) != attributes(element_b) {
+                    return divergence(
+                        format!(
+                            "attributes of <{}> are changed from [{}] to [{}]",
+                            element_a.tag_name,
+                            attributes(element_a),
+                            attributes(element_b)
+                        ),
+                        a.loc(),
+                        b.loc(),
+                    );
+                }
+
+                let children_divergence = compare_nodes(
+                    &element_a.children,
+                    &element_b.children,
+                    a.loc(),
+                    b.loc(),
+                    mode.of_children(element_a),
+                );
+                if children_divergence.is_some() {
+                    return children_divergence;
+                }
+            }
+            _ => {
+                return divergence(
+                    format!("{} is changed to {}", a.describe(), b.describe()),
+                    a.loc(),
+                    b.loc(),
+                )
+            }
+        }
+    }
+}
+
+fn describe(node: &Node<'_>) -> String {
+    match node {
+        Node::Element(element) => format!("element <{}>", element.tag_name),
+        Node::Text(text) => format!("text {:?}", text.significant()),
+        Node::Comment(comment) => format!("comment {:?}", comment.data.trim()),
+        Node::Doctype(doctype) => format!("doctype {:?}", doctype.data),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_equivalent, parse, Node};
+
+    #[test]
+    fn builds_nested_elements() {
+        let nodes = parse("<div><br><p class=\"a\">text</p><img/></span></div>").unwrap();
+
+        let Node::Element(div) = &nodes[0] else {
+            panic!("expected element");
+        };
+        let names: Vec<_> = div
+            .children
+            .iter()
+            .map(|node| match node {
+                Node::Element(element) => element.tag_name.to_string(),
+                Node::Text(text) => text.data.to_string(),
+                Node::Comment(comment) => comment.data.to_string(),
+                Node::Doctype(doctype) => doctype.data.to_string(),
+            })
+            .collect();
+
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(names, ["br", "p", "img"]);
+
+        let nodes = parse("<DIV>a</div><p>b</P>").unwrap();
+        assert_eq!(nodes.len(), 2);
+        for node in &nodes {
+            let Node::Element(element) = node else {
+                panic!("expected element");
+            };
+            assert_eq!(element.children.len(), 1);
+            assert!(element.end_loc.is_some());
+        }
+    }
+
+    #[test]
+    fn reports_first_divergence() {
+        assert!(check_equivalent("<p>a  b</p>\n", "<p>\n\ta\n\tb\n</p>\n").is_ok());
+        assert!(check_equivalent(
+            "<style>a{color:red}</style>",
+            "<style>\n\ta {\n\t\tcolor: red;\n\t}\n</style>\n"
+        )
+        .is_ok());
+
+        let error = check_equivalent(
+            "<div>\n<p title='say \"hi\"'>a</p></div>",
+            "<div>\n\t<p title=\"say \"hi\"\">\n\t\ta\n\t</p>\n</div>\n",
+        )
+        .unwrap_err();
+
+        let message = error.to_string();
+
+        assert!(message.starts_with("formatting changes the document: attributes of <p>"));
+        assert!(message.ends_with("(original 2:0, formatted 2:1)"));
+    }
+
+    #[test]
+    fn whitespace_is_significant_where_it_renders() {
+        let unsafe_changes = [
+            ("<pre>a\n  b</pre>", "<pre>\n\ta\n\tb\n</pre>\n"),
+            (
+                "<script>`a\n  b`</script>",
+                "<script>\n\t`a\n\tb`\n</script>\n",
+            ),
+            (
+                "<p>a<b>b</b>c</p>",
+                "<p>\n\ta\n\t<b>\n\t\tb\n\t</b>\n\tc\n</p>\n",
+            ),
+            (
+                "<style>.a .b{}</style>",
+                "<style>\n\t.a.b {\n\t}\n</style>\n",
+            ),
+            ("<!DOCTYPE html>", "<DOCTYPE html=\"\">\n"),
+        ];
+
+        for (original, formatted) in unsafe_changes {
+            assert!(
+                check_equivalent(original, formatted).is_err(),
+                "{:?}",
+                original
+            );
+        }
+
+        let safe_changes = [
+            (
+                "<div><p>a <b>b</b></p><ul> <li>c</li></ul></div>",
+                "<div>\n\t<p>\n\t\ta\n\t\t<b>b</b>\n\t</p>\n\t<ul>\n\t\t<li>c</li>\n\t</ul>\n</div>\n",
+            ),
+            (
+                "<div>a<ul><li>x</li></ul></div>",
+                "<div>\n\ta\n\t<ul>\n\t\t<li>\n\t\t\tx\n\t\t</li>\n\t</ul>\n</div>\n",
+            ),
+            (
+                "<fieldset><legend>a</legend>b</fieldset>",
+                "<fieldset>\n\t<legend>\n\t\ta\n\t</legend>\n\tb\n</fieldset>\n",
+            ),
+        ];
+
+        for (original, formatted) in safe_changes {
+            assert!(
+                check_equivalent(original, formatted).is_ok(),
+                "{:?}",
+                original
+            );
+        }
+    }
+}
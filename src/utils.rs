@@ -1,7 +1,50 @@
-pub fn is_whitespace(char: char) -> bool {
-    char.is_whitespace()
+use std::borrow::Cow;
+
+/// Elements without content and end tag
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose text is rendered with its whitespace, or is script source
+const PREFORMATTED_ELEMENTS: [&str; 3] = ["pre", "textarea", "script"];
+
+/// Converts CRLF and lone CR line endings to LF, borrowing when there is nothing to convert
+pub fn normalize_newlines(str: &str) -> Cow<'_, str> {
+    if str.contains('\r') {
+        Cow::Owned(str.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(str)
+    }
+}
+
+/// Quotes an attribute value with `"`, or with `'` if the value contains only `"`,
+/// otherwise `"` in the value is escaped as `&quot;`
+pub fn quote_attribute_value(value: &str) -> String {
+    if value.contains('"') && !value.contains('\'') {
+        format!("'{}'", value)
+    } else {
+        format!("\"{}\"", value.replace('"', "&quot;"))
+    }
+}
+
+/// Counts CRLF, LF and lone CR as line breaks, as `InputStream` does
+pub fn count_line_breaks(str: &str) -> usize {
+    str.matches('\n').count() + str.matches('\r').count() - str.matches("\r\n").count()
 }
 
 pub fn is_alphabet(char: char) -> bool {
     char.is_ascii_alphabetic()
 }
+
+pub fn is_void_element(tag_name: &str) -> bool {
+    VOID_ELEMENTS
+        .iter()
+        .any(|void_element| tag_name.eq_ignore_ascii_case(void_element))
+}
+
+pub fn is_preformatted_element(tag_name: &str) -> bool {
+    PREFORMATTED_ELEMENTS
+        .iter()
+        .any(|element| tag_name.eq_ignore_ascii_case(element))
+}
@@ -1,42 +1,932 @@
-use crate::config::Config;
-use crate::formatter::Formatter;
-use crate::input_stream::InputStream;
-use crate::lexer::Lexer;
+use crate::cli_error::CliError;
+use crate::config::{Config, ConfigFormat, Mode, OptionSource};
+use crate::config_file::ConfigFiles;
+use crate::diff;
+use crate::editorconfig::EditorConfigs;
+use crate::ignore_files::IgnoreFiles;
+use html_formatter::options::{Options, PartialOptions};
+use html_formatter::{check_equivalent, format_range, format_str, minify_str, FormatError};
+use std::collections::BTreeMap;
+use std::env;
 use std::fs;
+use std::io::{self, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+const STDIN_PATH: &str = "<stdin>";
+
+/// Some files are not formatted, returned only in check mode
+const EXIT_CHANGES_NEEDED: u8 = 1;
+/// Some files failed to be read, parsed or written
+const EXIT_ERRORS: u8 = 2;
 
 pub struct Walker {
     config: Config,
+    cwd: PathBuf,
+    config_files: ConfigFiles,
+    editorconfigs: EditorConfigs,
+}
+
+/// Result of formatting a single file. Files are formatted in parallel, so
+/// everything printed for a file is kept here and reported in input order.
+struct FileReport {
+    changed: bool,
+    diff: Option<String>,
+    /// Diff between the first and the second formatting pass in verify mode
+    unstable_diff: Option<String>,
 }
 
+/// Report of a file, or error of a pattern, with its path
+type PathReport = (String, Result<FileReport, CliError>);
+
 impl Walker {
     pub fn new(config: Config) -> Self {
         log::trace!("init new Walker with config {:#?}", config);
 
-        Walker { config }
+        Walker {
+            config,
+            cwd: env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            config_files: ConfigFiles::new(),
+            editorconfigs: EditorConfigs::new(),
+        }
     }
 }
 
 impl Walker {
-    pub fn run(&self) {
-        for path in &self.config.files {
-            self.format_file(path)
+    pub fn run(&self) -> ExitCode {
+        if let Some(path) = &self.config.print_config {
+            return self.print_config(Path::new(path));
+        }
+
+        let ignore_files = match IgnoreFiles::new(&self.config, &self.cwd) {
+            Ok(ignore_files) => ignore_files,
+            Err(error) => {
+                eprintln!("error: {}", error);
+
+                return ExitCode::from(EXIT_ERRORS);
+            }
+        };
+
+        let (files_count, reports) = self.format_inputs(&ignore_files);
+
+        self.print_reports(files_count, &reports)
+    }
+
+    /// Formats stdin or the collected files, returns the number of inputs and their
+    /// reports by path. Patterns which failed to expand are reported as errors.
+    fn format_inputs(&self, ignore_files: &IgnoreFiles) -> (usize, Vec<PathReport>) {
+        if self.config.stdin {
+            let report = self.format_stdin(ignore_files);
+
+            return (1, vec![(self.stdin_path().to_owned(), report)]);
+        }
+
+        let (files, errors) = self.collect_files(ignore_files);
+        let mut reports: Vec<_> = errors
+            .into_iter()
+            .map(|(pattern, error)| (pattern, Err(error)))
+            .collect();
+
+        reports.extend(
+            files
+                .iter()
+                .map(|path| path.display().to_string())
+                .zip(self.format_files(&files, |path| self.format_file(path))),
+        );
+
+        (files.len(), reports)
+    }
+
+    /// Prints diffs, errors and the summary, returns the exit code
+    fn print_reports(&self, files_count: usize, reports: &[PathReport]) -> ExitCode {
+        let mut unformatted_files = vec![];
+        let mut failed_files_count = 0;
+
+        for (path, report) in reports {
+            match report {
+                Ok(report) => {
+                    if let Some(diff) = &report.diff {
+                        print!("{}", diff);
+                    }
+
+                    // Unstable files are not written, so they only count as failed
+                    if let Some(unstable_diff) = &report.unstable_diff {
+                        eprintln!("error: {}: second formatting pass changed the file", path);
+                        eprint!("{}", unstable_diff);
+                        failed_files_count += 1;
+                    } else if report.changed {
+                        unformatted_files.push(path);
+                    }
+                }
+                Err(error) => {
+                    eprintln!("error: {}: {}", path, error);
+                    failed_files_count += 1;
+                }
+            }
+        }
+
+        if self.config.mode == Mode::Check {
+            for path in &unformatted_files {
+                println!("{}", path);
+            }
+        }
+
+        if !self.config.stdin {
+            self.print_summary(files_count, unformatted_files.len(), failed_files_count);
+        }
+
+        if failed_files_count > 0 {
+            ExitCode::from(EXIT_ERRORS)
+        } else if self.config.mode == Mode::Check && !unformatted_files.is_empty() {
+            ExitCode::from(EXIT_CHANGES_NEEDED)
+        } else {
+            ExitCode::SUCCESS
+        }
+    }
+
+    /// Expands directories and glob patterns of `config.files` into unique paths sorted
+    /// by their absolute path
+    fn collect_files(&self, ignore_files: &IgnoreFiles) -> (Vec<PathBuf>, Vec<(String, CliError)>) {
+        let mut files = BTreeMap::new();
+        let mut errors = vec![];
+
+        for pattern in &self.config.files {
+            if let Err(error) = self.collect_pattern(pattern, ignore_files, &mut files) {
+                errors.push((pattern.clone(), error));
+            }
+        }
+
+        (files.into_values().collect(), errors)
+    }
+
+    fn collect_pattern(
+        &self,
+        pattern: &str,
+        ignore_files: &IgnoreFiles,
+        files: &mut BTreeMap<PathBuf, PathBuf>,
+    ) -> Result<(), CliError> {
+        let path = Path::new(pattern);
+
+        // Existing paths win over patterns, file names may contain glob chars
+        if path.exists() || !pattern.contains(['*', '?', '[']) {
+            return self.collect_path(path, ignore_files, files);
+        }
+
+        let mut matched = false;
+
+        for path in glob::glob(pattern)? {
+            self.collect_path(&path?, ignore_files, files)?;
+            matched = true;
+        }
+
+        if !matched {
+            return Err(CliError::NoMatches);
+        }
+
+        Ok(())
+    }
+
+    /// Explicitly passed files are formatted regardless of their extension,
+    /// files found in directories are filtered by `config.extensions`
+    fn collect_path(
+        &self,
+        path: &Path,
+        ignore_files: &IgnoreFiles,
+        files: &mut BTreeMap<PathBuf, PathBuf>,
+    ) -> Result<(), CliError> {
+        let is_dir = path.is_dir();
+
+        if ignore_files.is_ignored(path, is_dir) {
+            log::trace!("skip ignored path {}", path.display());
+
+            return Ok(());
+        }
+
+        if !is_dir {
+            self.insert_file(path, files);
+
+            return Ok(());
         }
+
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if entry.file_type()?.is_dir() {
+                self.collect_path(&path, ignore_files, files)?;
+            } else if self.has_extension(&path) && !ignore_files.is_ignored(&path, false) {
+                self.insert_file(&path, files);
+            }
+        }
+
+        Ok(())
     }
 
-    fn format_file(&self, path: &String) {
-        log::trace!("start format file {}", path);
+    /// Files are keyed by their absolute path, so the same file passed as a relative
+    /// and an absolute path is formatted once
+    fn insert_file(&self, path: &Path, files: &mut BTreeMap<PathBuf, PathBuf>) {
+        let path = normalize_path(path);
+
+        files
+            .entry(normalize_path(&self.cwd.join(&path)))
+            .or_insert(path);
+    }
+
+    fn has_extension(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|extension| {
+            self.config
+                .extensions
+                .iter()
+                .any(|allowed| extension.eq_ignore_ascii_case(allowed.as_str()))
+        })
+    }
+
+    /// Formats files with `format_file` on `config.jobs` threads, reports are returned
+    /// in the order of files
+    fn format_files(
+        &self,
+        files: &[PathBuf],
+        format_file: impl Fn(&Path) -> Result<FileReport, CliError> + Sync,
+    ) -> Vec<Result<FileReport, CliError>> {
+        let jobs = self.config.jobs.clamp(1, files.len().max(1));
+        let next_index = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut reports = vec![];
+
+                        loop {
+                            let index = next_index.fetch_add(1, Ordering::Relaxed);
+                            let Some(path) = files.get(index) else {
+                                break;
+                            };
 
-        let content = fs::read_to_string(path).expect("Should have been able to read file");
+                            // A panic is reported as an error of its file only
+                            let report =
+                                panic::catch_unwind(AssertUnwindSafe(|| format_file(path)))
+                                    .unwrap_or_else(|payload| {
+                                        Err(CliError::Panic(panic_message(payload)))
+                                    });
+
+                            reports.push((index, report));
+                        }
+
+                        reports
+                    })
+                })
+                .collect();
+
+            let mut reports: Vec<_> = workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("Formatting thread panicked"))
+                .collect();
+            reports.sort_by_key(|(index, _)| *index);
+
+            reports.into_iter().map(|(_, report)| report).collect()
+        })
+    }
+
+    fn format_file(&self, path: &Path) -> Result<FileReport, CliError> {
+        log::trace!("start format file {}", path.display());
+
+        let bytes = fs::read(path)?;
+        let content = std::str::from_utf8(&bytes)?;
+        let options = self.resolve_options(path)?;
+        let formatted_content = self.format_content(content, &options)?;
+        let changed = formatted_content != content;
+        let unstable_diff =
+            self.verify(&path.display().to_string(), &formatted_content, &options)?;
+        let mut diff = None;
+
+        // Unstable output isn't written, it would change again on the next run
+        if changed && unstable_diff.is_none() {
+            match self.config.mode {
+                Mode::Write => fs::write(path, formatted_content)?,
+                Mode::Check => {}
+                Mode::Diff => {
+                    diff = Some(self.diff(&path.display().to_string(), content, &formatted_content))
+                }
+            }
+        }
+
+        log::trace!("finish format file {}", path.display());
+
+        Ok(FileReport {
+            changed,
+            diff,
+            unstable_diff,
+        })
+    }
 
-        let stream = InputStream::new(content.as_str());
-        let mut lexer = Lexer::new(stream);
+    /// Same as `format_file`, but formatted content is written to stdout in write mode
+    fn format_stdin(&self, ignore_files: &IgnoreFiles) -> Result<FileReport, CliError> {
+        log::trace!("start format stdin as {}", self.stdin_path());
 
-        let tokens = lexer.tokenize();
-        let mut formatter = Formatter::new(tokens.to_owned(), &self.config);
-        let content = formatter.format();
+        let mut bytes = vec![];
+        io::stdin().read_to_end(&mut bytes)?;
+        let content = std::str::from_utf8(&bytes)?;
+        let (formatted_content, report) = self.format_stdin_content(content, ignore_files)?;
+
+        if self.config.mode == Mode::Write {
+            io::stdout().write_all(formatted_content.as_bytes())?;
+        }
+
+        Ok(report)
+    }
+
+    /// Content of an ignored `--stdin-filepath` is returned unchanged
+    fn format_stdin_content(
+        &self,
+        content: &str,
+        ignore_files: &IgnoreFiles,
+    ) -> Result<(String, FileReport), CliError> {
+        let ignored = self
+            .config
+            .stdin_filepath
+            .as_ref()
+            .is_some_and(|path| ignore_files.is_ignored(Path::new(path), false));
+        let mut unstable_diff = None;
+        let formatted_content = if ignored {
+            content.to_owned()
+        } else {
+            let options = self.resolve_options(Path::new(self.stdin_path()))?;
+            let formatted_content = self.format_content(content, &options)?;
+            unstable_diff = self.verify(self.stdin_path(), &formatted_content, &options)?;
+
+            formatted_content
+        };
+        let changed = formatted_content != content;
+        let mut diff = None;
+
+        if changed && self.config.mode == Mode::Diff {
+            diff = Some(self.diff(self.stdin_path(), content, &formatted_content));
+        }
+
+        let report = FileReport {
+            changed,
+            diff,
+            unstable_diff,
+        };
+
+        Ok((formatted_content, report))
+    }
+
+    /// In safe mode content which doesn't build the same tree after formatting is an error
+    fn format_content(&self, content: &str, options: &Options) -> Result<String, CliError> {
+        let formatted_content = match &self.config.range {
+            Some(range) => format_range(content, range.resolve(content), options)?,
+            None => self.format_whole(content, options)?,
+        };
+
+        if self.config.safe {
+            check_equivalent(content, &formatted_content)?;
+        }
+
+        Ok(formatted_content)
+    }
+
+    /// Formats `formatted_content` again in verify mode, returns the diff if it changes
+    fn verify(
+        &self,
+        path: &str,
+        formatted_content: &str,
+        options: &Options,
+    ) -> Result<Option<String>, CliError> {
+        if !self.config.verify {
+            return Ok(None);
+        }
+
+        let reformatted_content = self.format_whole(formatted_content, options)?;
+
+        Ok((reformatted_content != formatted_content)
+            .then(|| self.diff(path, formatted_content, &reformatted_content)))
+    }
+
+    /// Minifies instead of formatting in minify mode
+    fn format_whole(&self, content: &str, options: &Options) -> Result<String, FormatError> {
+        match &self.config.minify {
+            Some(minify_options) => minify_str(content, minify_options),
+            None => format_str(content, options),
+        }
+    }
 
-        fs::write(path, content).expect("Unable to write file");
+    fn resolve_options(&self, path: &Path) -> Result<Options, CliError> {
+        let options = self
+            .option_layers(path)?
+            .iter()
+            .fold(Options::new(), |options, (_, layer)| options.merge(layer));
 
-        log::trace!("finish format file {}", path);
+        Ok(options)
+    }
+
+    /// Options of `.editorconfig` files, the nearest config file, its overrides
+    /// and CLI flags, later layers take precedence
+    fn option_layers(&self, path: &Path) -> Result<Vec<(OptionSource, PartialOptions)>, CliError> {
+        let path = self.cwd.join(path);
+        let mut layers = vec![];
+
+        if self.config.editorconfig {
+            for (editorconfig_path, options) in self.editorconfigs.options_for(&path)? {
+                layers.push((OptionSource::EditorConfig(editorconfig_path), options));
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            if let Some(config_file) = self.config_files.find(dir)? {
+                log::trace!(
+                    "use config file {} for {}",
+                    config_file.path.display(),
+                    path.display()
+                );
+
+                layers.push((
+                    OptionSource::ConfigFile(config_file.path.clone()),
+                    config_file.options.clone(),
+                ));
+
+                for (index, item) in config_file.overrides_for(&path) {
+                    log::trace!("apply override {:?} for {}", item.files, path.display());
+
+                    layers.push((
+                        OptionSource::Override {
+                            path: config_file.path.clone(),
+                            index,
+                        },
+                        item.options.clone(),
+                    ));
+                }
+            }
+        }
+
+        layers.push((OptionSource::Cli, self.config.options.clone()));
+
+        Ok(layers)
+    }
+
+    /// Prints options resolved for `path`, each with the source of its value
+    fn print_config(&self, path: &Path) -> ExitCode {
+        let values = match self.resolve_config(path) {
+            Ok(values) => values,
+            Err(error) => {
+                eprintln!("error: {}: {}", path.display(), error);
+
+                return ExitCode::from(EXIT_ERRORS);
+            }
+        };
+
+        match self.config.print_config_format {
+            ConfigFormat::Toml => {
+                for (key, (value, source)) in &values {
+                    let value = toml::Value::try_from(value).expect("Options are valid TOML");
+
+                    println!("{} = {} # {}", key, value, source);
+                }
+            }
+            ConfigFormat::Json => {
+                let sources: serde_json::Map<_, _> = values
+                    .iter()
+                    .map(|(key, (_, source))| (key.clone(), source.to_string().into()))
+                    .collect();
+                let values: serde_json::Map<_, _> = values
+                    .into_iter()
+                    .map(|(key, (value, _))| (key, value))
+                    .collect();
+                let output = serde_json::json!({ "options": values, "sources": sources });
+
+                println!("{:#}", output);
+            }
+        }
+
+        ExitCode::SUCCESS
+    }
+
+    /// Options resolved for `path` by name, with the source of each value
+    fn resolve_config(
+        &self,
+        path: &Path,
+    ) -> Result<BTreeMap<String, (serde_json::Value, OptionSource)>, CliError> {
+        let layers = self.option_layers(path)?;
+        let options = layers
+            .iter()
+            .fold(Options::new(), |options, (_, layer)| options.merge(layer));
+        let serde_json::Value::Object(values) =
+            serde_json::to_value(&options).expect("Options are serializable")
+        else {
+            unreachable!("Options are serialized as a map");
+        };
+
+        let values = values
+            .into_iter()
+            .map(|(key, value)| {
+                let source = layers
+                    .iter()
+                    .rev()
+                    .find(|(_, layer)| layer.keys().contains(&key.as_str()))
+                    .map_or(OptionSource::Default, |(source, _)| source.clone());
+
+                (key, (value, source))
+            })
+            .collect();
+
+        Ok(values)
+    }
+
+    fn diff(&self, path: &str, content: &str, formatted_content: &str) -> String {
+        diff::unified_diff(
+            path,
+            content,
+            formatted_content,
+            self.config.diff_context,
+            self.config.color,
+        )
+    }
+
+    fn print_summary(&self, files_count: usize, changed_count: usize, failed_count: usize) {
+        let changed = match self.config.mode {
+            Mode::Write => format!("{} of {} files formatted", changed_count, files_count),
+            Mode::Check | Mode::Diff => format!(
+                "{} of {} files would be reformatted",
+                changed_count, files_count
+            ),
+        };
+
+        if failed_count > 0 {
+            eprintln!("{}, {} failed", changed, failed_count);
+        } else {
+            eprintln!("{}", changed);
+        }
+    }
+
+    fn stdin_path(&self) -> &str {
+        self.config.stdin_filepath.as_deref().unwrap_or(STDIN_PATH)
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map_or("unknown panic".to_owned(), |message| (*message).to_owned()),
+    }
+}
+
+/// Resolves `.` and `..` components lexically, so `./a.html`, `sub/../a.html` and
+/// `a.html` are the same file
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_path, FileReport, Walker};
+    use crate::cli_error::CliError;
+    use crate::config::{Config, Mode, OptionSource};
+    use crate::config_file::ConfigFiles;
+    use crate::editorconfig::EditorConfigs;
+    use crate::ignore_files::IgnoreFiles;
+    use crate::temp_dir::TempDir;
+    use html_formatter::FormatError;
+    use serde_json::json;
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::process::ExitCode;
+    use std::thread;
+    use std::time::Duration;
+
+    fn walker(config: Config, cwd: &Path) -> Walker {
+        Walker {
+            config,
+            cwd: cwd.to_path_buf(),
+            config_files: ConfigFiles::new(),
+            editorconfigs: EditorConfigs::new(),
+        }
+    }
+
+    /// Collected paths relative to `dir`
+    fn collect(walker: &Walker, dir: &Path) -> Vec<String> {
+        let ignore_files = IgnoreFiles::new(&walker.config, &walker.cwd).unwrap();
+        let (files, errors) = walker.collect_files(&ignore_files);
+        assert!(errors.is_empty());
+
+        files
+            .iter()
+            .map(|path| {
+                path.strip_prefix(dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn normalizes_paths_lexically() {
+        let normalize = |path: &str| normalize_path(Path::new(path));
+
+        assert_eq!(normalize("./sub/../a.html"), Path::new("a.html"));
+        assert_eq!(normalize("../x/./a.html"), Path::new("../x/a.html"));
+        assert_eq!(normalize("/a/b/../../c.html"), Path::new("/c.html"));
+    }
+
+    #[test]
+    fn collect_expands_globs_and_directories() {
+        let dir = TempDir::new("collect_files");
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        dir.write("b.html", "");
+        dir.write("a.HTM", "");
+        dir.write("notes.txt", "");
+        dir.write("sub/c.html", "");
+        dir.write("sub/deep/d.html", "");
+        dir.write("sub/e.vue", "");
+
+        let path = |relative: &str| dir.path().join(relative).display().to_string();
+
+        // Directories are collected by extension, explicit files regardless of it,
+        // duplicates are formatted once and paths are sorted
+        let config = Config::new(vec![
+            path("sub/deep/../../sub"),
+            path("notes.txt"),
+            path("*.html"),
+            path("."),
+            path("sub/../b.html"),
+        ]);
+        assert_eq!(
+            collect(&walker(config, dir.path()), dir.path()),
+            [
+                "a.HTM",
+                "b.html",
+                "notes.txt",
+                "sub/c.html",
+                "sub/deep/d.html"
+            ]
+        );
+
+        let config = Config::new(vec![path("**/*.vue")]);
+        assert_eq!(
+            collect(&walker(config, dir.path()), dir.path()),
+            ["sub/e.vue"]
+        );
+
+        let config = Config::new(vec![path("sub")]).set_extensions(vec!["vue".to_owned()]);
+        assert_eq!(
+            collect(&walker(config, dir.path()), dir.path()),
+            ["sub/e.vue"]
+        );
+
+        let typo = walker(Config::new(vec![path("nomatch/**/*.html")]), dir.path());
+        let ignore_files = IgnoreFiles::new(&typo.config, &typo.cwd).unwrap();
+        let (files, errors) = typo.collect_files(&ignore_files);
+        assert!(files.is_empty());
+        assert!(matches!(errors[..], [(_, CliError::NoMatches)]));
+
+        // Missing files are kept to be reported when read
+        let config = Config::new(vec![path("missing.html")]);
+        assert_eq!(
+            collect(&walker(config, dir.path()), dir.path()),
+            ["missing.html"]
+        );
+    }
+
+    #[test]
+    fn collect_skips_ignored_paths() {
+        let dir = TempDir::new("collect_ignored");
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        dir.write(".htmlformatterignore", "dist/\n");
+        dir.write("a.html", "");
+        dir.write("dist/b.html", "");
+        dir.write("sub/.gitignore", "vendor/\n");
+        dir.write("sub/c.html", "");
+        dir.write("sub/vendor/d.html", "");
+
+        let root = dir.path().display().to_string();
+        let config = Config::new(vec![root.clone()]).set_ignore(vec![], true);
+        assert_eq!(
+            collect(&walker(config, dir.path()), dir.path()),
+            ["a.html", "sub/c.html"]
+        );
+
+        // Explicitly passed ignored files are skipped too
+        let vendor_file = dir.path().join("sub/vendor/d.html");
+        let config = Config::new(vec![vendor_file.display().to_string()]).set_ignore(vec![], true);
+        assert!(collect(&walker(config, dir.path()), dir.path()).is_empty());
+
+        let config = Config::new(vec![root]);
+        assert_eq!(
+            collect(&walker(config, dir.path()), dir.path()),
+            ["a.html", "sub/c.html", "sub/vendor/d.html"]
+        );
+    }
+
+    #[test]
+    fn check_mode_does_not_write() {
+        let dir = TempDir::new("check_mode");
+        let content = "<div><p>text</p></div>";
+        let path = dir.write("a.html", content);
+
+        let check = walker(Config::new(vec![]).set_mode(Mode::Check), dir.path());
+        assert!(check.format_file(&path).unwrap().changed);
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+
+        let write = walker(Config::new(vec![]), dir.path());
+        assert!(write.format_file(&path).unwrap().changed);
+        assert!(!check.format_file(&path).unwrap().changed);
+    }
+
+    #[test]
+    fn stdin_filepath_applies_ignore_rules() {
+        let dir = TempDir::new("stdin_ignored");
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        dir.write(".gitignore", "vendor/\n");
+        let content = "<div><p>text</p></div>";
+
+        let format = |stdin_filepath: &str| {
+            let config = Config::new(vec![])
+                .set_ignore(vec![], true)
+                .set_stdin(true, Some(stdin_filepath.to_owned()));
+            let walker = walker(config, dir.path());
+            let ignore_files = IgnoreFiles::new(&walker.config, &walker.cwd).unwrap();
+
+            walker.format_stdin_content(content, &ignore_files).unwrap()
+        };
+
+        let (output, report) = format("vendor/a.html");
+        assert_eq!(output, content);
+        assert!(!report.changed);
+
+        let (output, report) = format("src/a.html");
+        assert_ne!(output, content);
+        assert!(report.changed);
+    }
+
+    #[test]
+    fn print_config_names_the_source_of_each_value() {
+        let dir = TempDir::new("print_config");
+        let editorconfig = dir.write(
+            ".editorconfig",
+            "root = true\n\n[*]\nindent_style = tab\nindent_size = 8\n",
+        );
+        let config_file = dir.write(
+            "htmlformatter.toml",
+            "indent_size = 4\n\n[[overrides]]\nfiles = \"emails/*.html\"\noptions = { max_line_length = 100 }\n",
+        );
+
+        let config = Config::new(vec![]).set_end_of_line(Some("crlf".to_owned()));
+        let values = walker(config, dir.path())
+            .resolve_config(Path::new("emails/a.html"))
+            .unwrap();
+
+        assert_eq!(
+            values["indent_style"].1,
+            OptionSource::EditorConfig(editorconfig)
+        );
+        assert_eq!(
+            values["indent_size"],
+            (json!(4), OptionSource::ConfigFile(config_file.clone()))
+        );
+        assert_eq!(
+            values["max_line_length"],
+            (
+                json!(100),
+                OptionSource::Override {
+                    path: config_file,
+                    index: 0
+                }
+            )
+        );
+        assert_eq!(values["end_of_line"].1, OptionSource::Cli);
+        assert_eq!(values["max_blank_lines"].1, OptionSource::Default);
+    }
+
+    #[test]
+    fn failed_files_do_not_stop_others() {
+        let dir = TempDir::new("failed_files");
+        let content = "<div><p>text</p></div>";
+        let valid = dir.write("a.html", content);
+        let non_utf8 = dir.write("b.html", "");
+        fs::write(&non_utf8, [0xff, 0xfe]).unwrap();
+        let unterminated = dir.write("c.html", "<div");
+
+        let root = dir.path().display().to_string();
+        let write = walker(Config::new(vec![root]), dir.path());
+        let ignore_files = IgnoreFiles::new(&write.config, &write.cwd).unwrap();
+        let (files_count, reports) = write.format_inputs(&ignore_files);
+
+        assert_eq!(files_count, 3);
+        assert_ne!(fs::read_to_string(&valid).unwrap(), content);
+        assert!(reports[0].1.is_ok());
+        assert_eq!(reports[1].0, non_utf8.display().to_string());
+        assert!(matches!(reports[1].1, Err(CliError::InvalidUtf8(_))));
+        assert_eq!(reports[2].0, unterminated.display().to_string());
+        assert!(matches!(
+            reports[2].1,
+            Err(CliError::Format(FormatError::Parse(_)))
+        ));
+        assert_eq!(
+            write.print_reports(files_count, &reports),
+            ExitCode::from(2)
+        );
+
+        // Only changes needed in check mode
+        fs::write(&valid, content).unwrap();
+        let config = Config::new(vec![valid.display().to_string()]).set_mode(Mode::Check);
+        let check = walker(config, dir.path());
+        let (files_count, reports) = check.format_inputs(&ignore_files);
+        assert_eq!(
+            check.print_reports(files_count, &reports),
+            ExitCode::from(1)
+        );
+        assert_eq!(fs::read_to_string(&valid).unwrap(), content);
+    }
+
+    /// Index of a `<index>.html` file
+    fn file_index(path: &Path) -> u64 {
+        path.file_stem().unwrap().to_str().unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn parallel_reports_keep_input_order() {
+        let files: Vec<_> = (0..10)
+            .map(|i| PathBuf::from(format!("{}.html", i)))
+            .collect();
+        let walker = walker(Config::new(vec![]).set_jobs(Some(3)), Path::new("."));
+
+        // Later files finish first
+        let reports = walker.format_files(&files, |path| {
+            let index = file_index(path);
+            thread::sleep(Duration::from_millis(10 - index));
+
+            Ok(FileReport {
+                changed: false,
+                diff: Some(path.display().to_string()),
+                unstable_diff: None,
+            })
+        });
+
+        let diffs: Vec<_> = reports
+            .into_iter()
+            .map(|report| report.unwrap().diff.unwrap())
+            .collect();
+        assert_eq!(
+            diffs,
+            files
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn panicking_file_does_not_stop_others() {
+        let files: Vec<_> = (0..5)
+            .map(|i| PathBuf::from(format!("{}.html", i)))
+            .collect();
+        let walker = walker(Config::new(vec![]).set_jobs(Some(2)), Path::new("."));
+
+        let reports = walker.format_files(&files, |path| {
+            if file_index(path) == 2 {
+                panic!("broken {}", path.display());
+            }
+
+            Ok(FileReport {
+                changed: false,
+                diff: None,
+                unstable_diff: None,
+            })
+        });
+
+        assert_eq!(reports.len(), 5);
+        for (index, report) in reports.iter().enumerate() {
+            match report {
+                Err(CliError::Panic(message)) => {
+                    assert_eq!(index, 2);
+                    assert_eq!(message, "broken 2.html");
+                }
+                report => assert!(report.is_ok()),
+            }
+        }
     }
 }